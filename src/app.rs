@@ -1,87 +1,358 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::anyhow;
+use itertools::Itertools;
 
 use crate::{
-    configuration::{Settings, get_db_file},
+    configuration::{Settings, SettingsBuilder, get_backup_dir, get_db_file, load_settings},
+    error::LoadError,
+    history::{Action, Change, History},
+    store::{self, TaskStore},
     task::Task,
-    utils,
+    workspace,
 };
 
 pub type Id = usize;
 
 pub struct App {
-    pub tasks: HashMap<Id, Task>,
+    pub store: Box<dyn TaskStore>,
     pub settings: Settings,
-    pub current_id: usize,
+    history: History,
+    /// Collects the changes of an action made of several operations, see `App::as_one_action`
+    pending: Option<Action>,
+    settings_modified: Option<SystemTime>,
+}
+
+/// When the file at `path` was last modified, if it exists
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
 }
 
 impl App {
-    pub fn new(settings: Settings) -> App {
-        let tasks: HashMap<Id, Task> = utils::load_tasks(get_db_file());
-        let current_id = tasks.iter().map(|(&k, _)| k).max().unwrap_or(0);
+    pub fn new(settings: Settings) -> Result<App, LoadError> {
+        let store = Self::open_store(&settings, &settings.workspace)?;
+        let settings_modified = SettingsBuilder::get_settings_path()
+            .ok()
+            .and_then(|path| modified(&path));
+        let history = Self::history(&settings, &settings.workspace)?;
+        Ok(App {
+            store,
+            settings,
+            history,
+            pending: None,
+            settings_modified,
+        })
+    }
+
+    /// An app working on `store` instead of the database of the active workspace
+    pub fn with_store(settings: Settings, store: Box<dyn TaskStore>, history: History) -> App {
         App {
-            tasks,
+            store,
             settings,
-            current_id,
+            history,
+            pending: None,
+            settings_modified: None,
+        }
+    }
+
+    fn open_store(settings: &Settings, workspace: &str) -> Result<Box<dyn TaskStore>, LoadError> {
+        store::open(
+            settings.storage,
+            &get_db_file(workspace, settings.storage)?,
+            &get_backup_dir(workspace)?,
+            settings.backup_count,
+        )
+    }
+
+    fn history(settings: &Settings, workspace: &str) -> Result<History, LoadError> {
+        Ok(History::for_db(&get_db_file(workspace, settings.storage)?))
+    }
+
+    pub fn workspace(&self) -> &str {
+        &self.settings.workspace
+    }
+
+    /// Makes `workspace` the active one, loading its tasks
+    pub fn switch_workspace(&mut self, workspace: &str) -> anyhow::Result<()> {
+        workspace::ensure_available()?;
+        if !workspace::exists(workspace)? {
+            return Err(anyhow!("Workspace '{}' not found", workspace));
+        }
+        self.store = Self::open_store(&self.settings, workspace)?;
+        self.history = Self::history(&self.settings, workspace)?;
+        self.settings.set_workspace(workspace)
+    }
+
+    /// Re-reads the settings file if it was modified since it was last read, applying the
+    /// settings that can change while running. Returns whether any of them changed.
+    pub fn reload_settings(&mut self) -> anyhow::Result<bool> {
+        let path = SettingsBuilder::get_settings_path()?;
+        let modified = modified(&path);
+        if modified == self.settings_modified {
+            return Ok(false);
         }
+
+        // Remember the new version even if it fails to load, so the error is only reported once
+        self.settings_modified = modified;
+        let (settings, _) = load_settings(&path)?;
+        Ok(self.settings.reload_from(settings))
+    }
+
+    pub fn tasks(&self) -> &HashMap<Id, Task> {
+        self.store.tasks()
     }
 
     pub fn get_task(&self, id: Id) -> Option<&Task> {
-        self.tasks.get(&id)
+        self.store.get(id)
+    }
+
+    /// Records the changes of an action so it can be undone
+    fn record(&mut self, description: String, changes: Vec<Change>) -> anyhow::Result<()> {
+        match &mut self.pending {
+            Some(action) => {
+                action.changes.extend(changes);
+                Ok(())
+            }
+            None => self.history.record(Action {
+                description,
+                changes,
+            }),
+        }
+    }
+
+    /// Runs `f`, recording everything it changes as a single action that is undone at once
+    pub fn as_one_action<T>(
+        &mut self,
+        description: String,
+        f: impl FnOnce(&mut App) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        // Nested actions are part of the outer one
+        if self.pending.is_some() {
+            return f(self);
+        }
+
+        self.pending = Some(Action {
+            description,
+            changes: vec![],
+        });
+        let result = f(self);
+        // Changes made before a failure are already saved, so they are recorded too
+        if let Some(action) = self.pending.take() {
+            self.history.record(action)?;
+        }
+        result
+    }
+
+    /// Reverts the last recorded action, returning its description
+    pub fn undo(&mut self) -> anyhow::Result<Option<String>> {
+        let action = self.history.undo(self.store.as_mut())?;
+        Ok(action.map(|a| a.description))
+    }
+
+    /// Applies the last undone action again, returning its description
+    pub fn redo(&mut self) -> anyhow::Result<Option<String>> {
+        let action = self.history.redo(self.store.as_mut())?;
+        Ok(action.map(|a| a.description))
     }
 
-    pub fn save_state(&mut self) {
-        utils::save_tasks(get_db_file(), self);
+    /// The tasks that are steps of the task with `id`
+    pub fn subtasks(&self, id: Id) -> Vec<&Task> {
+        self.tasks()
+            .values()
+            .filter(|t| t.parent == Some(id) && t.id != Some(id))
+            .collect()
     }
 
-    pub fn add_task(&mut self, mut t: Task) -> Id {
-        let new_id = match t.id {
-            Some(id) => {
-                self.tasks.insert(id, t);
-                self.save_state();
-                id
+    /// The subtasks of the task with `id`, their subtasks and so on
+    pub fn descendants(&self, id: Id) -> Vec<Id> {
+        let mut descendants = vec![];
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            for task in self.subtasks(current) {
+                let child = task.id.unwrap_or_default();
+                if child != id && !descendants.contains(&child) {
+                    descendants.push(child);
+                    stack.push(child);
+                }
             }
-            None => {
-                let new_id = self.get_next_id();
-                t.id = Some(new_id);
-                self.tasks.insert(new_id, t);
-                new_id
+        }
+        descendants
+    }
+
+    /// The tasks that wait for the task with `id`
+    pub fn dependents(&self, id: Id) -> Vec<&Task> {
+        self.tasks()
+            .values()
+            .filter(|t| t.blocked_by.contains(&id))
+            .sorted_by_key(|t| t.id)
+            .collect()
+    }
+
+    /// Whether any of the tasks `task` waits for isn't complete yet
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        task.blocked_by
+            .iter()
+            .any(|&id| self.get_task(id).is_some_and(|t| !t.complete))
+    }
+
+    /// Checks that the tasks `task` waits for exist, and that none of them waits for `task`,
+    /// directly or through other tasks
+    fn check_blockers(&self, task: &Task) -> anyhow::Result<()> {
+        for &blocker_id in &task.blocked_by {
+            if task.id == Some(blocker_id) {
+                return Err(anyhow!("'{}' can't wait for itself", task.name));
             }
-        };
-        self.save_state();
-        new_id
+            let blocker = self
+                .get_task(blocker_id)
+                .ok_or_else(|| anyhow!("Task with id {} not found", blocker_id))?;
+
+            let Some(id) = task.id else {
+                continue;
+            };
+            let mut stack = vec![blocker_id];
+            let mut seen = HashSet::new();
+            while let Some(current) = stack.pop() {
+                if current == id {
+                    return Err(anyhow!(
+                        "'{}' can't wait for '{}', which already waits for it",
+                        task.name,
+                        blocker.name
+                    ));
+                }
+                if seen.insert(current)
+                    && let Some(t) = self.get_task(current)
+                {
+                    stack.extend(&t.blocked_by);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_task(&mut self, t: Task) -> anyhow::Result<Id> {
+        self.check_blockers(&t)?;
+        self.insert_task(t)
     }
 
-    pub fn delete_task(&mut self, id: usize) -> Option<Id> {
-        self.tasks.remove(&id)?;
-        self.save_state();
-        Some(id)
+    fn insert_task(&mut self, t: Task) -> anyhow::Result<Id> {
+        let description = format!("add '{}'", t.name);
+        let id = self.store.insert(t)?;
+        let task = self.get_task(id).cloned().expect("Task was just added");
+        self.record(description, vec![Change::Insert(task)])?;
+        Ok(id)
     }
 
-    pub fn set_complete(&mut self, id: usize, complete: bool) -> Option<Id> {
-        let new_task_id = if complete {
-            let possible_new_task = self.tasks.get_mut(&id)?.set_complete();
-            if let Some(possible_new_task) = possible_new_task {
-                self.delete_task(id);
-                self.add_task(possible_new_task)
-            } else {
-                id
+    pub fn delete_task(&mut self, id: usize) -> anyhow::Result<Option<Id>> {
+        let Some(task) = self.get_task(id) else {
+            return Ok(None);
+        };
+        let description = format!("delete '{}'", task.name);
+        let parent = task.parent;
+        self.as_one_action(description.clone(), |app| {
+            // Its subtasks move up to its own parent, and the tasks waiting for it stop waiting
+            let subtasks: Vec<Task> = app.subtasks(id).into_iter().cloned().collect();
+            for mut subtask in subtasks {
+                subtask.set_parent(parent);
+                app.replace_task(subtask)?;
+            }
+            let dependents: Vec<Task> = app.dependents(id).into_iter().cloned().collect();
+            for mut dependent in dependents {
+                dependent.blocked_by.retain(|&blocker| blocker != id);
+                app.replace_task(dependent)?;
             }
+
+            let Some(task) = app.store.delete(id)? else {
+                return Ok(None);
+            };
+            app.record(description, vec![Change::Delete(task)])?;
+            Ok(Some(id))
+        })
+    }
+
+    /// Replaces a task with a new version of it, keeping its id
+    pub fn update_task(&mut self, task: Task) -> anyhow::Result<Id> {
+        let id = task
+            .id
+            .ok_or_else(|| anyhow!("Cannot update a task without an id"))?;
+        if self.get_task(id).is_none() {
+            return Err(anyhow!("Task with id {} not found", id));
+        }
+        self.check_blockers(&task)?;
+        self.replace_task(task)?;
+        Ok(id)
+    }
+
+    fn replace_task(&mut self, task: Task) -> anyhow::Result<Option<Id>> {
+        let Some(id) = task.id else {
+            return Ok(None);
+        };
+        let Some(before) = self.get_task(id).cloned() else {
+            return Ok(None);
+        };
+        self.store.update(task.clone())?;
+        let description = format!("edit '{}'", task.name);
+        self.record(description, vec![Change::update(before, task)])?;
+        Ok(Some(id))
+    }
+
+    pub fn set_complete(&mut self, id: usize, complete: bool) -> anyhow::Result<Option<Id>> {
+        let Some(before) = self.get_task(id).cloned() else {
+            return Ok(None);
+        };
+        let mut after = before.clone();
+        let next = if complete {
+            after.set_complete()
         } else {
-            self.tasks.get_mut(&id)?.set_incomplete();
-            id
+            after.set_incomplete()
         };
 
-        self.save_state();
-        Some(new_task_id)
+        // Completing an occurrence again must not add a second copy of the next one
+        let has_next = before.series.is_some()
+            && self.tasks().values().any(|t| {
+                t.series == before.series
+                    && t.id != before.id
+                    && !t.complete
+                    && t.date > before.date
+            });
+
+        let verb = if complete { "complete" } else { "uncomplete" };
+        let description = format!("{} '{}'", verb, before.name);
+        self.as_one_action(description.clone(), |app| {
+            app.store.update(after.clone())?;
+            app.record(description, vec![Change::update(before, after)])?;
+
+            // The completed occurrence stays as history, next to the new one
+            if let Some(next) = next.filter(|_| !has_next) {
+                app.insert_task(next)?;
+            }
+            Ok(Some(id))
+        })
     }
 
-    pub fn toggle_complete_task(&mut self, id: usize) -> Option<Id> {
-        let complete = self.tasks.get(&id)?.complete;
-        self.set_complete(id, !complete)
+    /// Completes a task along with all of its subtasks that aren't complete yet
+    pub fn complete_with_subtasks(&mut self, id: usize) -> anyhow::Result<Option<Id>> {
+        let Some(task) = self.get_task(id) else {
+            return Ok(None);
+        };
+        let description = format!("complete '{}' and its subtasks", task.name);
+        self.as_one_action(description, |app| {
+            for subtask in app.descendants(id) {
+                if app.get_task(subtask).is_some_and(|t| !t.complete) {
+                    app.set_complete(subtask, true)?;
+                }
+            }
+            app.set_complete(id, true)
+        })
     }
 
-    fn get_next_id(&mut self) -> usize {
-        self.current_id += 1;
-        self.current_id
+    pub fn toggle_complete_task(&mut self, id: usize) -> anyhow::Result<Option<Id>> {
+        let Some(task) = self.get_task(id) else {
+            return Ok(None);
+        };
+        let complete = task.complete;
+        self.set_complete(id, !complete)
     }
 }
@@ -1,5 +1,6 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use clap::Parser;
+use itertools::Itertools;
 
 use crate::app::App;
 use crate::cli::cli_utils;
@@ -10,15 +11,32 @@ use crate::task_form::TaskForm;
 pub struct Args {
     /// The name of the new task
     name: String,
-    /// The date the task is due
+    /// The date the task is due, e.g. 16-10-2026, 'tomorrow' or 'next fri 9am'
     #[arg(long)]
     date: Option<String>,
+    /// The date to start the task on, hidden until then, e.g. 'next month'
+    #[arg(long)]
+    start: Option<String>,
     /// How often the task repeats
     #[arg(long)]
     repeats: Option<String>,
+    /// How important the task is: none, low, medium, high or urgent
+    #[arg(long)]
+    priority: Option<String>,
     /// The group the task belongs to
     #[arg(long)]
     group: Option<String>,
+    /// The ID of the task this is a subtask of
+    #[arg(long)]
+    parent: Option<usize>,
+    /// A tag for the task, can be given more than once. Words like +home or #home in the name
+    /// are tags too.
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+    /// The ID of a task that has to be complete before this one can start, can be given more
+    /// than once
+    #[arg(long = "blocked-by")]
+    blocked_by: Vec<usize>,
     /// A description or url for your task
     #[arg(long)]
     description: Option<String>,
@@ -35,24 +53,43 @@ pub fn run(mut app: App, args: Args) -> Result<()> {
         name,
         format,
         date,
+        start,
         repeats,
+        priority,
         group,
+        parent,
+        tags,
+        blocked_by,
         description,
         url,
     } = args;
 
     let mut task_form = TaskForm {
         id: None,
+        series: None,
+        parent,
         name,
         date: date.unwrap_or("".to_string()),
+        start: start.unwrap_or("".to_string()),
         repeats: repeats.unwrap_or("".to_string()),
+        priority: priority.unwrap_or("".to_string()),
         group: group.unwrap_or("".to_string()),
+        tags: tags.join(", "),
+        blocked_by: blocked_by.iter().map(|id| id.to_string()).join(", "),
         description: description.unwrap_or("".to_string()),
         url: url.unwrap_or("".to_string()),
+        complete: false,
+        completed_at: None,
     };
 
+    if let Some(parent) = parent
+        && app.get_task(parent).is_none()
+    {
+        return Err(anyhow!("Task with id {} not found", parent));
+    }
+
     let task = task_form.submit(&app.settings)?;
-    let id = app.add_task(task);
+    let id = app.add_task(task)?;
     let task = app.get_task(id).unwrap();
     cli_utils::print_task(task, format, &app.settings);
 
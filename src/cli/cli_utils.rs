@@ -1,5 +1,13 @@
 use super::formats::Format;
-use crate::{configuration::Settings, task::Task, utils};
+use crate::{
+    configuration::Settings,
+    due::Due,
+    priority::Priority,
+    repeat::Repeat,
+    task::{self, Task},
+    utils,
+};
+use chrono::Utc;
 
 pub fn print_task(task: &Task, format: Option<Format>, settings: &Settings) {
     let tasks = vec![task];
@@ -22,21 +30,38 @@ pub fn print_tasks(
             "{}",
             serde_json::to_string_pretty(&tasks).expect("Failed to serialize tasks to JSON")
         ),
+        Some(Format::Ical) => print_ical(&tasks),
         _ => {
-            let longest_name = tasks.iter().map(|t| t.name.len()).max().unwrap_or(0);
+            // Subtasks are indented below their parent
+            let tasks = task::as_tree(tasks);
+            let longest_name = tasks
+                .iter()
+                .map(|(t, depth)| t.name.len() + 2 * depth)
+                .max()
+                .unwrap_or(0);
             let longest_date = tasks
                 .iter()
-                .map(|t| utils::date_to_display_str(&t.date, settings).len())
+                .map(|(t, _)| date_to_str(t, settings).len())
                 .max()
                 .unwrap_or(0);
             let longest_repeat = tasks
                 .iter()
-                .map(|t| t.repeats.to_string().len())
+                .map(|(t, _)| t.repeats.to_string().len())
+                .max()
+                .unwrap_or(0);
+            let longest_priority = tasks
+                .iter()
+                .map(|(t, _)| t.priority.to_string().len())
                 .max()
                 .unwrap_or(0);
             let longest_group = tasks
                 .iter()
-                .map(|t| t.group.as_deref().unwrap_or_default().len())
+                .map(|(t, _)| t.group.as_deref().unwrap_or_default().len())
+                .max()
+                .unwrap_or(0);
+            let longest_tags = tasks
+                .iter()
+                .map(|(t, _)| tags_to_str(t).len())
                 .max()
                 .unwrap_or(0);
 
@@ -44,7 +69,9 @@ pub fn print_tasks(
             print!("{:width$}  ", "Name", width = longest_name + 10);
             print!("{:width$}  ", "Date", width = longest_date);
             print!("{:width$}\t", "Repeats", width = longest_repeat);
+            print!("{:width$}\t", "Priority", width = longest_priority);
             print!("{:width$}\t", "Group", width = longest_group);
+            print!("{:width$}\t", "Tags", width = longest_tags);
 
             if show_descriptions {
                 print!("Description  ");
@@ -56,24 +83,31 @@ pub fn print_tasks(
             println!();
 
             // Print tasks
-            for task in tasks {
+            for (task, depth) in tasks {
                 let complete = task.complete;
                 let x = settings.icons.get_complete_icon(complete);
                 let name = task.name.clone();
                 let id = task.id.unwrap();
-                let name_id = format!("{} {} ({})", x, name, id);
+                let indent = "  ".repeat(depth);
+                let name_id = format!("{}{} {} ({})", indent, x, name, id);
                 let width = longest_name + 10;
                 print!("{:width$}  ", name_id, width = width);
 
-                let date = utils::date_to_display_str(&task.date, settings);
+                let date = date_to_str(task, settings);
                 print!("{:width$}  ", date, width = longest_date);
 
                 let repeats = &task.repeats;
                 print!("{:width$}\t", repeats, width = longest_repeat);
 
+                let priority = task.priority.to_string();
+                print!("{:width$}\t", priority, width = longest_priority);
+
                 let group = task.group.as_deref().unwrap_or_default();
                 print!("{:width$}\t", group, width = longest_group);
 
+                let tags = tags_to_str(task);
+                print!("{:width$}\t", tags, width = longest_tags);
+
                 if show_descriptions {
                     let description = task.description.clone();
                     print!("{}  ", description.unwrap_or(String::from("")));
@@ -89,3 +123,154 @@ pub fn print_tasks(
         }
     }
 }
+
+fn date_to_str(task: &Task, settings: &Settings) -> String {
+    task.date
+        .as_ref()
+        .map(|date| utils::date_to_display_str(date, settings))
+        .unwrap_or_default()
+}
+
+fn tags_to_str(task: &Task) -> String {
+    task.tags
+        .iter()
+        .map(|tag| format!("#{}", tag))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Prints the tasks as a VCALENDAR with one VTODO each
+fn print_ical(tasks: &[&Task]) {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//gyst-tui//EN".to_string(),
+    ];
+    for task in tasks {
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:{}", ical_uid(task)));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        if let Some(parent) = tasks.iter().find(|t| t.id.is_some() && t.id == task.parent) {
+            lines.push(format!("RELATED-TO;RELTYPE=PARENT:{}", ical_uid(parent)));
+        }
+        for blocker in tasks
+            .iter()
+            .filter(|t| t.id.is_some_and(|id| task.blocked_by.contains(&id)))
+        {
+            lines.push(format!(
+                "RELATED-TO;RELTYPE=DEPENDS-ON:{}",
+                ical_uid(blocker)
+            ));
+        }
+        lines.push(format!("SUMMARY:{}", escape_ical(&task.name)));
+        if let Some(date) = task.date {
+            lines.push(ical_date("DUE", &date));
+        }
+        // Occurrences are counted from DTSTART, so repeating tasks start when they're due at the
+        // latest
+        let start = match task.repeats {
+            Repeat::Never => task.start,
+            _ => task.start.or(task.date),
+        };
+        if let Some(start) = start.and_then(|start| ical_start(start, task.date)) {
+            lines.push(ical_date("DTSTART", &start));
+            if let Some(rrule) = task.repeats.to_rrule(task.date.as_ref()) {
+                lines.push(format!("RRULE:{}", rrule));
+            }
+        }
+        if task.priority != Priority::None {
+            lines.push(format!("PRIORITY:{}", task.priority.to_ical()));
+        }
+        if task.complete {
+            lines.push("STATUS:COMPLETED".to_string());
+            if let Some(completed_at) = task.completed_at {
+                lines.push(format!(
+                    "COMPLETED:{}",
+                    completed_at.naive_utc().format("%Y%m%dT%H%M%SZ")
+                ));
+            }
+        } else {
+            lines.push("STATUS:NEEDS-ACTION".to_string());
+        }
+        let categories: Vec<String> = task
+            .group
+            .iter()
+            .chain(&task.tags)
+            .map(|c| escape_ical(c))
+            .collect();
+        if !categories.is_empty() {
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+        if let Some(description) = &task.description {
+            lines.push(format!("DESCRIPTION:{}", escape_ical(description)));
+        }
+        if let Some(url) = &task.url {
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("END:VTODO".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    for line in lines {
+        print!("{}\r\n", fold_ical(&line));
+    }
+}
+
+/// Splits a content line into lines of at most 75 octets, each continued line starting with a
+/// space
+fn fold_ical(line: &str) -> String {
+    let mut folded = String::new();
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded
+}
+
+/// `start` as a date or a time like `due`, as DTSTART and DUE must have the same type
+fn ical_start(start: Due, due: Option<Due>) -> Option<Due> {
+    match (start, due) {
+        (Due::DateTime { .. }, Some(Due::Date(_))) => Some(Due::Date(start.zoned_date())),
+        (Due::Date(day), Some(due @ Due::DateTime { .. })) => due.on(day),
+        _ => Some(start),
+    }
+}
+
+fn ical_uid(task: &Task) -> String {
+    match task.series {
+        Some(series) => format!("{}-{}@gyst-tui", series, task.id.unwrap_or_default()),
+        None => format!("{}@gyst-tui", task.id.unwrap_or_default()),
+    }
+}
+
+/// A date property like `DUE`, in the task's time zone if it has one
+fn ical_date(property: &str, due: &Due) -> String {
+    match due {
+        Due::Date(date) => format!("{};VALUE=DATE:{}", property, date.format("%Y%m%d")),
+        Due::DateTime {
+            at,
+            zone: Some(zone),
+        } => format!(
+            "{};TZID={}:{}",
+            property,
+            zone.name(),
+            at.with_timezone(zone).format("%Y%m%dT%H%M%S")
+        ),
+        Due::DateTime { at, zone: None } => {
+            format!("{}:{}", property, at.format("%Y%m%dT%H%M%SZ"))
+        }
+    }
+}
+
+fn escape_ical(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
@@ -11,6 +11,9 @@ pub struct Args {
     /// Whether the task should be marked as complete or incomplete
     #[arg(short, long)]
     complete: CompleteStatus,
+    /// Also complete all the subtasks of the task
+    #[arg(long)]
+    subtasks: bool,
     /// The format to print the updated task with
     #[arg(short, long)]
     format: Option<Format>,
@@ -26,6 +29,7 @@ pub fn run(mut app: App, args: Args) -> Result<()> {
     let Args {
         id,
         complete,
+        subtasks,
         format,
     } = args;
     let complete_bool = match complete {
@@ -33,7 +37,11 @@ pub fn run(mut app: App, args: Args) -> Result<()> {
         CompleteStatus::Incomplete => false,
     };
 
-    let task_id = app.set_complete(id, complete_bool);
+    let task_id = if complete_bool && subtasks {
+        app.complete_with_subtasks(id)?
+    } else {
+        app.set_complete(id, complete_bool)?
+    };
     match task_id {
         Some(task_id) => {
             let task = app.get_task(task_id).unwrap();
@@ -1,9 +1,15 @@
-use crate::{app::App, configuration::SettingsBuilder};
+use crate::{
+    app::App,
+    configuration::{SettingsBuilder, load_settings},
+    utils,
+};
 use anyhow::Result;
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 pub struct Args {
+    #[command(subcommand)]
+    command: Option<ConfigCommand>,
     /// Reset the configuration to default
     #[clap(long)]
     reset: bool,
@@ -18,6 +24,13 @@ pub struct Args {
     icons: Option<Icons>,
 }
 
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Rewrites the settings file with every field, filling in missing ones with their
+    /// defaults and dropping the ones that are not recognized
+    Migrate,
+}
+
 #[derive(Parser, Clone, Copy, ValueEnum)]
 enum Icons {
     /// Set the icons to special characters
@@ -36,12 +49,17 @@ enum Mode {
 
 pub fn run(mut app: App, args: Args) -> Result<()> {
     let Args {
+        command,
         reset,
         show,
         mode,
         icons,
     } = args;
 
+    if let Some(ConfigCommand::Migrate) = command {
+        return migrate();
+    }
+
     if reset {
         let mut sb = SettingsBuilder::default();
         sb.save_to_file()?;
@@ -49,14 +67,14 @@ pub fn run(mut app: App, args: Args) -> Result<()> {
     }
 
     match mode {
-        Some(Mode::Vi) => app.settings.set_vi_mode(),
-        Some(Mode::Normal) => app.settings.set_normal_mode(),
+        Some(Mode::Vi) => app.settings.set_vi_mode()?,
+        Some(Mode::Normal) => app.settings.set_normal_mode()?,
         None => {}
     }
 
     match icons {
-        Some(Icons::Special) => app.settings.set_special_icons(),
-        Some(Icons::Chars) => app.settings.set_char_icons(),
+        Some(Icons::Special) => app.settings.set_special_icons()?,
+        Some(Icons::Chars) => app.settings.set_char_icons()?,
         None => {}
     }
 
@@ -66,3 +84,17 @@ pub fn run(mut app: App, args: Args) -> Result<()> {
 
     Ok(())
 }
+
+fn migrate() -> Result<()> {
+    let path = SettingsBuilder::get_settings_path()?;
+    let (settings, unknown) = load_settings(&path)?;
+    for field in unknown {
+        println!("Removed unknown setting '{}'", field);
+    }
+
+    let settings_json = serde_json::to_string_pretty(&settings)?;
+    utils::atomic_write(&path, settings_json.as_bytes())?;
+    println!("Migrated {}", path.display());
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use crate::app::App;
+use crate::configuration::{get_backup_dir, get_db_file, has_db_override};
+use crate::store::{self, Storage};
+use crate::workspace;
+use anyhow::{Result, anyhow};
+use clap::{Parser, Subcommand, ValueEnum};
+
+#[derive(Parser)]
+pub struct Args {
+    #[command(subcommand)]
+    command: DbCommand,
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Copies your todos to another storage backend and starts using it
+    Migrate {
+        /// The storage backend to move to
+        #[arg(long)]
+        to: Backend,
+        /// Replace any todos already stored in the target backend
+        #[arg(long)]
+        overwrite: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Backend {
+    Json,
+    Sqlite,
+}
+
+impl Backend {
+    fn to_storage(self) -> Storage {
+        match self {
+            Backend::Json => Storage::Json,
+            Backend::Sqlite => Storage::Sqlite,
+        }
+    }
+}
+
+pub fn run(mut app: App, args: Args) -> Result<()> {
+    let Args { command } = args;
+    match command {
+        DbCommand::Migrate { to, overwrite } => migrate(&mut app, to.to_storage(), overwrite),
+    }
+}
+
+/// Every workspace shares the storage setting, so the todos of all of them are copied
+fn migrate(app: &mut App, to: Storage, overwrite: bool) -> Result<()> {
+    let from = app.settings.storage;
+    if from == to {
+        return Err(anyhow!("Your todos are already stored with {:?}", to));
+    }
+    if has_db_override() {
+        return Err(anyhow!(
+            "A database given with --db or GYST_DB is used exactly as given, so it can't be migrated"
+        ));
+    }
+
+    // Open everything before copying anything, so a conflict leaves every workspace as it was
+    let mut copies = vec![];
+    for workspace in workspace::list()? {
+        let path = get_db_file(&workspace, to)?;
+        let backup_dir = get_backup_dir(&workspace)?;
+        let source = store::open(
+            from,
+            &get_db_file(&workspace, from)?,
+            &backup_dir,
+            app.settings.backup_count,
+        )?;
+        let target = store::open(to, &path, &backup_dir, app.settings.backup_count)?;
+        if !target.tasks().is_empty() && !overwrite {
+            return Err(anyhow!(
+                "{} already contains todos, use --overwrite to replace them",
+                path.display()
+            ));
+        }
+        copies.push((workspace, path, source, target));
+    }
+
+    for (workspace, path, source, mut target) in copies {
+        let tasks = source.tasks().values().cloned().collect::<Vec<_>>();
+        let count = tasks.len();
+        target.replace_all(tasks)?;
+        println!(
+            "Copied {} todos of workspace '{}' to {}",
+            count,
+            workspace,
+            path.display()
+        );
+    }
+    app.settings.set_storage(to)
+}
@@ -20,7 +20,7 @@ pub fn run(mut app: App, args: Args) -> Result<()> {
         println!("Task with id {} not found", id);
     }
 
-    let task_id = app.delete_task(id);
+    let task_id = app.delete_task(id)?;
 
     match task_id {
         Some(_) => {
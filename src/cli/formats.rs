@@ -5,4 +5,6 @@ pub enum Format {
     Json,
     JsonPretty,
     PlainText,
+    /// iCalendar to-dos, for importing into calendar tools
+    Ical,
 }
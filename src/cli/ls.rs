@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use crate::app::{App, Id};
 use crate::configuration::Settings;
+use crate::due::Due;
+use crate::search::Search;
 use crate::task::Task;
 use crate::utils;
 use anyhow::Result;
@@ -18,6 +20,9 @@ pub struct Args {
     /// Whether to show complete tasks
     #[arg(short, long)]
     show_complete: bool,
+    /// Whether to show tasks that haven't reached their start date
+    #[arg(long)]
+    show_deferred: bool,
     /// Whether to show task descriptions
     #[arg(long)]
     show_descriptions: bool,
@@ -27,12 +32,36 @@ pub struct Args {
     /// Filter tasks by relative date
     #[arg(long)]
     date_filter: Option<DateFilter>,
-    /// Filter tasks by date
+    /// Filter tasks by date, e.g. 16-10-2026 or 'tomorrow'
     #[arg(long)]
     date: Option<String>,
+    /// Only show tasks without a date
+    #[arg(long, conflicts_with_all = ["date_filter", "date"])]
+    no_date: bool,
     /// Filter by group
     #[arg(long)]
     group: Option<String>,
+    /// Filter by tag, can be given more than once to show the tasks with all of them
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+    /// Show the tasks with any of the tags instead
+    #[arg(long, requires = "tags")]
+    any_tag: bool,
+    /// Only show tasks waiting for another task to be complete
+    #[arg(long)]
+    blocked: bool,
+    /// Only show tasks that aren't waiting for any other task
+    #[arg(long, conflicts_with = "blocked")]
+    actionable: bool,
+    /// Only show tasks with the text in their name, description, url or group
+    #[arg(long)]
+    search: Option<String>,
+    /// Search with a regular expression instead of plain text
+    #[arg(long, requires = "search")]
+    regex: bool,
+    /// Ignore case when searching
+    #[arg(short, long, requires = "search")]
+    ignore_case: bool,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -54,22 +83,26 @@ pub fn filter_by_relative_date(
             .into_iter()
             .filter(|(_, t)| {
                 let today = now.date_naive();
-                t.date.date_naive() == today
+                t.date.is_some_and(|d| d.date_naive() == today)
             })
             .collect(),
-        Some(DateFilter::Past) => tasks.into_iter().filter(|(_, t)| t.date < now).collect(),
+        Some(DateFilter::Past) => tasks
+            .into_iter()
+            .filter(|(_, t)| t.date.is_some_and(|d| d.deadline() < now))
+            .collect(),
         Some(DateFilter::TodayAndPast) => tasks
             .into_iter()
             .filter(|(_, t)| {
                 let today = now.date_naive();
-                t.date.date_naive() <= today
+                t.date.is_some_and(|d| d.date_naive() <= today)
             })
             .collect(),
         Some(DateFilter::Next24) => tasks
             .into_iter()
             .filter(|(_, t)| {
                 let tomorrow = now + chrono::Duration::days(1);
-                t.date >= now && t.date < tomorrow
+                t.date
+                    .is_some_and(|d| d.deadline() >= now && d.deadline() < tomorrow)
             })
             .collect(),
         _ => tasks,
@@ -83,14 +116,46 @@ pub fn filter_by_exact_date(
 ) -> Result<HashMap<Id, Task>> {
     let tasks = match date {
         Some(date) => {
+            // A day matches every task due that day, with or without a time
             let date = utils::parse_date(date.as_str(), settings)?;
-            tasks.into_iter().filter(|(_, t)| t.date == date).collect()
+            tasks
+                .into_iter()
+                .filter(|(_, t)| match date {
+                    Due::Date(day) => t.date.is_some_and(|d| d.date_naive() == day),
+                    Due::DateTime { at, .. } => {
+                        matches!(t.date, Some(Due::DateTime { at: task_at, .. }) if task_at == at)
+                    }
+                })
+                .collect()
         }
         None => tasks,
     };
     Ok(tasks)
 }
 
+pub fn filter_by_no_date(tasks: HashMap<Id, Task>, no_date: bool) -> HashMap<Id, Task> {
+    if no_date {
+        tasks
+            .into_iter()
+            .filter(|(_, t)| t.date.is_none())
+            .collect()
+    } else {
+        tasks
+    }
+}
+
+pub fn filter_by_start(tasks: HashMap<Id, Task>, show_deferred: bool) -> HashMap<Id, Task> {
+    if show_deferred {
+        tasks
+    } else {
+        let now = chrono::Local::now();
+        tasks
+            .into_iter()
+            .filter(|(_, t)| !t.is_deferred(now))
+            .collect()
+    }
+}
+
 pub fn filter_by_group(tasks: HashMap<Id, Task>, group: Option<String>) -> HashMap<Id, Task> {
     match group {
         Some(group) => {
@@ -109,30 +174,96 @@ pub fn filter_by_group(tasks: HashMap<Id, Task>, group: Option<String>) -> HashM
     }
 }
 
+pub fn filter_by_tags(tasks: HashMap<Id, Task>, tags: &[String], any: bool) -> HashMap<Id, Task> {
+    if tags.is_empty() {
+        return tasks;
+    }
+    let tags: Vec<&str> = tags
+        .iter()
+        .map(|t| t.trim_start_matches(['#', '+']))
+        .collect();
+    tasks
+        .into_iter()
+        .filter(|(_, t)| {
+            if any {
+                tags.iter().any(|tag| t.has_tag(tag))
+            } else {
+                tags.iter().all(|tag| t.has_tag(tag))
+            }
+        })
+        .collect()
+}
+
+pub fn filter_by_blocked(
+    tasks: HashMap<Id, Task>,
+    app: &App,
+    blocked: bool,
+    actionable: bool,
+) -> HashMap<Id, Task> {
+    if !blocked && !actionable {
+        return tasks;
+    }
+    tasks
+        .into_iter()
+        .filter(|(_, t)| app.is_blocked(t) == blocked)
+        .collect()
+}
+
+pub fn filter_by_search(tasks: HashMap<Id, Task>, search: Option<&Search>) -> HashMap<Id, Task> {
+    match search {
+        Some(search) => tasks
+            .into_iter()
+            .filter(|(_, t)| search.matches(t))
+            .collect(),
+        None => tasks,
+    }
+}
+
 pub fn run(app: App, args: Args) -> Result<()> {
     let Args {
         format,
         show_complete,
+        show_deferred,
         show_descriptions,
         show_urls,
         date_filter,
         date,
+        no_date,
         group,
+        tags,
+        any_tag,
+        blocked,
+        actionable,
+        search,
+        regex,
+        ignore_case,
     } = args;
+    let search = search
+        .map(|query| Search::new(&query, regex, ignore_case))
+        .transpose()?;
 
     let tasks: HashMap<Id, Task> = if !show_complete {
-        app.tasks.into_iter().filter(|(_, t)| !t.complete).collect()
+        app.tasks()
+            .iter()
+            .filter(|(_, t)| !t.complete)
+            .map(|(&id, t)| (id, t.clone()))
+            .collect()
     } else {
-        app.tasks
+        app.tasks().clone()
     };
 
+    let tasks = filter_by_start(tasks, show_deferred);
     let tasks = filter_by_relative_date(tasks, date_filter);
     let tasks = filter_by_exact_date(tasks, date, &app.settings)?;
+    let tasks = filter_by_no_date(tasks, no_date);
     let tasks = filter_by_group(tasks, group);
+    let tasks = filter_by_tags(tasks, &tags, any_tag);
+    let tasks = filter_by_blocked(tasks, &app, blocked, actionable);
+    let tasks = filter_by_search(tasks, search.as_ref());
 
     let mut tasks_vec = tasks.values().collect::<Vec<_>>();
 
-    tasks_vec.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.name.cmp(&b.name)));
+    tasks_vec.sort_by(|a, b| a.cmp_day_and_priority(b).then_with(|| a.name.cmp(&b.name)));
 
     cli_utils::print_tasks(
         tasks_vec,
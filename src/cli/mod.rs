@@ -1,26 +1,39 @@
 use crate::app::App;
 use anyhow::Result;
 use clap::Parser;
+use std::path::PathBuf;
 
 mod add;
 mod cli_utils;
 mod complete;
 mod config;
+mod db;
 mod delete;
 mod ls;
+mod restore;
+mod stats;
+mod undo;
+mod workspace;
 
 // Shared enums and structs
 mod formats;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
-struct Args {
+pub struct Args {
+    /// The task database to use [env: GYST_DB]
+    #[arg(long, global = true)]
+    pub db: Option<PathBuf>,
+    /// The directory holding settings.json [env: GYST_CONFIG_DIR]
+    #[arg(long, global = true)]
+    pub config_dir: Option<PathBuf>,
+    /// Runs the TUI if omitted
     #[command(subcommand)]
-    command: Command,
+    pub command: Option<Command>,
 }
 
 #[derive(Parser)]
-enum Command {
+pub enum Command {
     /// Lists all the tasks
     Ls(ls::Args),
     /// Adds a task to your todos
@@ -31,16 +44,29 @@ enum Command {
     Complete(complete::Args),
     /// Sets default configurations
     Config(config::Args),
+    /// Lists or restores backups of your todos
+    Restore(restore::Args),
+    /// Manages where your todos are stored
+    Db(db::Args),
+    /// Manages separate lists of todos
+    Workspace(workspace::Args),
+    /// Shows the completion history and streaks of a task
+    Stats(stats::Args),
+    /// Undoes the last change to your todos, even one made in a previous session
+    Undo(undo::Args),
 }
 
-pub fn start_cli(app: App) -> Result<()> {
-    let args = Args::parse();
-
-    match args.command {
+pub fn start_cli(app: App, command: Command) -> Result<()> {
+    match command {
         Command::Ls(args) => ls::run(app, args),
         Command::Add(args) => add::run(app, args),
         Command::Delete(args) => delete::run(app, args),
         Command::Complete(args) => complete::run(app, args),
         Command::Config(args) => config::run(app, args),
+        Command::Restore(args) => restore::run(app, args),
+        Command::Db(args) => db::run(app, args),
+        Command::Workspace(args) => workspace::run(app, args),
+        Command::Stats(args) => stats::run(app, args),
+        Command::Undo(args) => undo::run(app, args),
     }
 }
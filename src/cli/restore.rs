@@ -0,0 +1,59 @@
+use crate::app::App;
+use crate::configuration::{get_backup_dir, get_db_file};
+use crate::store::Storage;
+use crate::utils;
+use anyhow::{Result, anyhow};
+use clap::Parser;
+
+#[derive(Parser)]
+pub struct Args {
+    /// The backup to restore, either its number in the list or its file name. Lists the
+    /// available backups if omitted
+    backup: Option<String>,
+}
+
+pub fn run(app: App, args: Args) -> Result<()> {
+    let Args { backup } = args;
+    if app.settings.storage != Storage::Json {
+        return Err(anyhow!("Backups are only kept for the JSON storage"));
+    }
+
+    let backup_dir = get_backup_dir(app.workspace())?;
+    let backups = utils::list_backups(&backup_dir)?;
+
+    let Some(backup) = backup else {
+        if backups.is_empty() {
+            println!("No backups found in {}", backup_dir.display());
+            return Ok(());
+        }
+
+        for (idx, path) in backups.iter().enumerate() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let date = utils::backup_date(path)
+                .map(|d| utils::datetime_to_display_str(&d, &app.settings))
+                .unwrap_or_default();
+            println!("{:>3}  {}  {}", idx + 1, name, date);
+        }
+        return Ok(());
+    };
+
+    let path = match backup.parse::<usize>() {
+        Ok(idx) => backups.get(idx.wrapping_sub(1)),
+        Err(_) => backups
+            .iter()
+            .find(|p| p.file_name().is_some_and(|n| n.to_string_lossy() == backup)),
+    }
+    .ok_or_else(|| anyhow!("Backup '{}' not found", backup))?;
+
+    utils::restore_backup(
+        path,
+        &get_db_file(app.workspace(), app.settings.storage)?,
+        &backup_dir,
+    )?;
+    println!(
+        "Restored tasks from {}",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anyhow::{Result, anyhow};
+use chrono::Local;
+use clap::Parser;
+
+use crate::{app::App, repeat::Repeat, stats::Stats, utils};
+
+/// How many weeks of history are shown
+const HEATMAP_WEEKS: usize = 12;
+
+#[derive(Parser)]
+pub struct Args {
+    /// The ID of the task, or of any occurrence of a repeating task
+    #[arg(short, long)]
+    task: usize,
+}
+
+pub fn run(app: App, args: Args) -> Result<()> {
+    let task = app
+        .get_task(args.task)
+        .ok_or_else(|| anyhow!("Task with id {} not found", args.task))?;
+    let stats = Stats::for_task(app.tasks(), args.task).expect("Task exists");
+
+    println!("{} ({})", task.name, task.repeats);
+    println!("Completed:      {} times", stats.completions.len());
+    if task.repeats != Repeat::Never {
+        println!("Current streak: {}", stats.current_streak);
+        println!("Longest streak: {}", stats.longest_streak);
+    }
+    if let Some(last) = stats.completions.last() {
+        println!(
+            "Last completed: {}",
+            utils::datetime_to_display_str(last, &app.settings)
+        );
+    }
+
+    println!();
+    let today = Local::now().date_naive();
+    let days = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    for (day, row) in days.iter().zip(stats.heatmap(today, HEATMAP_WEEKS)) {
+        let cells: Vec<&str> = row
+            .iter()
+            .map(|count| match count {
+                Some(0) => "·",
+                Some(_) => "■",
+                None => " ",
+            })
+            .collect();
+        println!("{} {}", day, cells.join(" "));
+    }
+
+    Ok(())
+}
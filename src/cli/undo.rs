@@ -0,0 +1,25 @@
+use crate::app::App;
+use anyhow::Result;
+use clap::Parser;
+
+#[derive(Parser)]
+pub struct Args {
+    /// Redo the last undone change instead
+    #[arg(long)]
+    redo: bool,
+}
+
+pub fn run(mut app: App, args: Args) -> Result<()> {
+    if args.redo {
+        match app.redo()? {
+            Some(action) => println!("Redid {}", action),
+            None => println!("Nothing to redo"),
+        }
+    } else {
+        match app.undo()? {
+            Some(action) => println!("Undid {}", action),
+            None => println!("Nothing to undo"),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use crate::app::App;
+use crate::workspace;
+use anyhow::{Result, anyhow};
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+pub struct Args {
+    #[command(subcommand)]
+    command: WorkspaceCommand,
+}
+
+#[derive(Subcommand)]
+enum WorkspaceCommand {
+    /// Lists all the workspaces
+    Ls,
+    /// Creates a new, empty workspace
+    New {
+        /// The name of the new workspace
+        name: String,
+    },
+    /// Makes a workspace the active one
+    Switch {
+        /// The name of the workspace to switch to
+        name: String,
+    },
+    /// Deletes a workspace along with all of its todos
+    Rm {
+        /// The name of the workspace to delete
+        name: String,
+    },
+}
+
+pub fn run(mut app: App, args: Args) -> Result<()> {
+    let Args { command } = args;
+    workspace::ensure_available()?;
+    match command {
+        WorkspaceCommand::Ls => {
+            for name in workspace::list()? {
+                let marker = if name == app.workspace() { "*" } else { " " };
+                println!("{} {}", marker, name);
+            }
+        }
+        WorkspaceCommand::New { name } => {
+            workspace::create(&name)?;
+            println!("Created workspace '{}'", name);
+        }
+        WorkspaceCommand::Switch { name } => {
+            app.switch_workspace(&name)?;
+            println!("Switched to workspace '{}'", name);
+        }
+        WorkspaceCommand::Rm { name } => {
+            if name == app.workspace() {
+                return Err(anyhow!(
+                    "Workspace '{}' is active, switch to another one first",
+                    name
+                ));
+            }
+            workspace::remove(&name)?;
+            app.settings.remove_workspace_state(&name)?;
+            println!("Removed workspace '{}'", name);
+        }
+    }
+
+    Ok(())
+}
@@ -1,17 +1,25 @@
+use crate::error::LoadError;
+use crate::priority::Priority;
+use crate::store::Storage;
 use crate::utils;
-use anyhow::{Result, anyhow};
-use crossterm::event::KeyCode;
+use crate::workspace::DEFAULT_WORKSPACE;
+use anyhow::{Context, Result, anyhow};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::fs::OpenOptions;
-use std::io::prelude::*;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
 pub struct Icons {
     pub complete: String,
     pub incomplete: String,
     pub repeats: String,
+    pub priority: String,
+    pub blocked: String,
 }
 
 impl Icons {
@@ -31,6 +39,8 @@ impl Icons {
             complete: "󰄴".to_string(),
             incomplete: "󰝦".to_string(),
             repeats: "".to_string(),
+            priority: "".to_string(),
+            blocked: "".to_string(),
         }
     }
 }
@@ -41,6 +51,8 @@ impl Default for Icons {
             complete: "[x]".to_string(),
             incomplete: "[ ]".to_string(),
             repeats: "[r]".to_string(),
+            priority: "!".to_string(),
+            blocked: "[b]".to_string(),
         }
     }
 }
@@ -61,7 +73,7 @@ where
     Ok(color.to_tui_color())
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum Color {
     Reset,
     Black,
@@ -134,12 +146,64 @@ impl Color {
     }
 }
 
-pub fn deserialize_key<'de, D>(deserializer: D) -> Result<KeyCode, D::Error>
+/// A key, optionally pressed together with modifiers such as Ctrl
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub const fn new(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    pub const fn ctrl(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::CONTROL,
+        }
+    }
+}
+
+impl PartialEq<KeyBinding> for KeyEvent {
+    fn eq(&self, other: &KeyBinding) -> bool {
+        // Shift is already part of the character, e.g. 'N'
+        let modifiers = match self.code {
+            KeyCode::Char(_) => self.modifiers - KeyModifiers::SHIFT,
+            _ => self.modifiers,
+        };
+        self.code == other.code && modifiers == other.modifiers
+    }
+}
+
+pub fn deserialize_key<'de, D>(deserializer: D) -> Result<KeyBinding, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    match s.to_lowercase().as_ref() {
+    let mut key = s.as_str();
+    let mut modifiers = KeyModifiers::NONE;
+    loop {
+        let (modifier, rest) = match key.split_once('-') {
+            Some((m, rest)) if m.eq_ignore_ascii_case("ctrl") && !rest.is_empty() => {
+                (KeyModifiers::CONTROL, rest)
+            }
+            Some((m, rest)) if m.eq_ignore_ascii_case("alt") && !rest.is_empty() => {
+                (KeyModifiers::ALT, rest)
+            }
+            _ => break,
+        };
+        modifiers |= modifier;
+        key = rest;
+    }
+
+    // Only named keys ignore case, as 'N' is a different key than 'n'
+    let code = match key.to_lowercase().as_str() {
+        _ if key.chars().count() == 1 => Ok(KeyCode::Char(key.chars().next().unwrap())),
         "esc" => Ok(KeyCode::Esc),
         "backspace" => Ok(KeyCode::Backspace),
         "left" => Ok(KeyCode::Left),
@@ -176,59 +240,89 @@ where
         "menu" => Ok(KeyCode::Menu),
         "keypadbegin" => Ok(KeyCode::KeypadBegin),
         "enter" => Ok(KeyCode::Enter),
-        c if c.len() == 1 => Ok(KeyCode::Char(c.chars().next().unwrap())),
         _ => Err(serde::de::Error::custom("Invalid key")),
-    }
+    }?;
+    Ok(KeyBinding { code, modifiers })
 }
 
-pub fn serialize_key<S>(key: &KeyCode, serializer: S) -> Result<S::Ok, S::Error>
+pub fn serialize_key<S>(key: &KeyBinding, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
     let key = KeyBindings::key_to_str(key);
     key.serialize(serializer)
 }
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
 pub struct KeyBindings {
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub quit: KeyCode,
+    pub quit: KeyBinding,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub down: KeyBinding,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub up: KeyBinding,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub complete_task: KeyBinding,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub toggle_completed_tasks: KeyBinding,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub toggle_deferred_tasks: KeyBinding,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub bump_priority: KeyBinding,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub filter_tags: KeyBinding,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub search: KeyBinding,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub next_match: KeyBinding,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub prev_match: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub down: KeyCode,
+    pub delete_task: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub up: KeyCode,
+    pub new_task: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub complete_task: KeyCode,
+    pub new_subtask: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub toggle_completed_tasks: KeyCode,
+    pub toggle_subtasks: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub delete_task: KeyCode,
+    pub edit_task: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub new_task: KeyCode,
+    pub save_changes: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub edit_task: KeyCode,
+    pub enter_insert_mode: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub save_changes: KeyCode,
+    pub enter_normal_mode: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub enter_insert_mode: KeyCode,
+    pub enter_visual_mode: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub enter_normal_mode: KeyCode,
+    pub enter_command_mode: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub enter_visual_mode: KeyCode,
+    pub go_back: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub enter_command_mode: KeyCode,
+    pub open_link: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub go_back: KeyCode,
+    pub next_group: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub open_link: KeyCode,
+    pub prev_group: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub next_group: KeyCode,
+    pub switch_workspace: KeyBinding,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
-    pub prev_group: KeyCode,
+    pub undo: KeyBinding,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub redo: KeyBinding,
 }
 
 impl KeyBindings {
-    pub fn key_to_str(key: &KeyCode) -> String {
-        match key {
+    pub fn key_to_str(key: &KeyBinding) -> String {
+        let mut modifiers = String::new();
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            modifiers.push_str("Ctrl-");
+        }
+        if key.modifiers.contains(KeyModifiers::ALT) {
+            modifiers.push_str("Alt-");
+        }
+        let code = match key.code {
             KeyCode::Esc => "Esc".to_string(),
             KeyCode::Backspace => "Backspace".to_string(),
             KeyCode::Left => "Left".to_string(),
@@ -258,28 +352,40 @@ impl KeyBindings {
             KeyCode::Enter => "Enter".to_string(),
             KeyCode::Char(c) => c.to_string(),
             _ => "Unknown".to_string(),
-        }
+        };
+        modifiers + &code
     }
 
     pub fn get_vi_default() -> Self {
         Self {
-            quit: KeyCode::Char('q'),
-            down: KeyCode::Char('j'),
-            up: KeyCode::Char('k'),
-            complete_task: KeyCode::Char(' '),
-            toggle_completed_tasks: KeyCode::Char('c'),
-            delete_task: KeyCode::Char('d'),
-            new_task: KeyCode::Char('n'),
-            edit_task: KeyCode::Char('e'),
-            save_changes: KeyCode::Enter,
-            enter_insert_mode: KeyCode::Char('i'),
-            enter_normal_mode: KeyCode::Esc,
-            enter_visual_mode: KeyCode::Char('v'),
-            enter_command_mode: KeyCode::Char(':'),
-            go_back: KeyCode::Esc,
-            open_link: KeyCode::Enter,
-            next_group: KeyCode::Char('l'),
-            prev_group: KeyCode::Char('h'),
+            quit: KeyBinding::new(KeyCode::Char('q')),
+            down: KeyBinding::new(KeyCode::Char('j')),
+            up: KeyBinding::new(KeyCode::Char('k')),
+            complete_task: KeyBinding::new(KeyCode::Char(' ')),
+            toggle_completed_tasks: KeyBinding::new(KeyCode::Char('c')),
+            toggle_deferred_tasks: KeyBinding::new(KeyCode::Char('f')),
+            bump_priority: KeyBinding::new(KeyCode::Char('p')),
+            filter_tags: KeyBinding::new(KeyCode::Char('t')),
+            search: KeyBinding::new(KeyCode::Char('/')),
+            next_match: KeyBinding::new(KeyCode::Char('n')),
+            prev_match: KeyBinding::new(KeyCode::Char('N')),
+            delete_task: KeyBinding::new(KeyCode::Char('d')),
+            new_task: KeyBinding::new(KeyCode::Char('n')),
+            new_subtask: KeyBinding::new(KeyCode::Char('a')),
+            toggle_subtasks: KeyBinding::new(KeyCode::Tab),
+            edit_task: KeyBinding::new(KeyCode::Char('e')),
+            save_changes: KeyBinding::new(KeyCode::Enter),
+            enter_insert_mode: KeyBinding::new(KeyCode::Char('i')),
+            enter_normal_mode: KeyBinding::new(KeyCode::Esc),
+            enter_visual_mode: KeyBinding::new(KeyCode::Char('v')),
+            enter_command_mode: KeyBinding::new(KeyCode::Char(':')),
+            go_back: KeyBinding::new(KeyCode::Esc),
+            open_link: KeyBinding::new(KeyCode::Enter),
+            next_group: KeyBinding::new(KeyCode::Char('l')),
+            prev_group: KeyBinding::new(KeyCode::Char('h')),
+            switch_workspace: KeyBinding::new(KeyCode::Char('w')),
+            undo: KeyBinding::new(KeyCode::Char('u')),
+            redo: KeyBinding::ctrl(KeyCode::Char('r')),
         }
     }
 }
@@ -287,28 +393,40 @@ impl KeyBindings {
 impl Default for KeyBindings {
     fn default() -> Self {
         Self {
-            quit: KeyCode::Char('q'),
-            down: KeyCode::Down,
-            up: KeyCode::Up,
-            complete_task: KeyCode::Char(' '),
-            toggle_completed_tasks: KeyCode::Char('h'),
-            delete_task: KeyCode::Delete,
-            new_task: KeyCode::Char('n'),
-            edit_task: KeyCode::Char('e'),
-            save_changes: KeyCode::Enter,
-            enter_insert_mode: KeyCode::Char('i'),
-            enter_normal_mode: KeyCode::Esc,
-            enter_visual_mode: KeyCode::Char('v'),
-            enter_command_mode: KeyCode::Char(':'),
-            go_back: KeyCode::Esc,
-            open_link: KeyCode::Enter,
-            next_group: KeyCode::Right,
-            prev_group: KeyCode::Left,
+            quit: KeyBinding::new(KeyCode::Char('q')),
+            down: KeyBinding::new(KeyCode::Down),
+            up: KeyBinding::new(KeyCode::Up),
+            complete_task: KeyBinding::new(KeyCode::Char(' ')),
+            toggle_completed_tasks: KeyBinding::new(KeyCode::Char('h')),
+            toggle_deferred_tasks: KeyBinding::new(KeyCode::Char('f')),
+            bump_priority: KeyBinding::new(KeyCode::Char('p')),
+            filter_tags: KeyBinding::new(KeyCode::Char('t')),
+            search: KeyBinding::new(KeyCode::Char('/')),
+            next_match: KeyBinding::new(KeyCode::Char('n')),
+            prev_match: KeyBinding::new(KeyCode::Char('N')),
+            delete_task: KeyBinding::new(KeyCode::Delete),
+            new_task: KeyBinding::new(KeyCode::Char('n')),
+            new_subtask: KeyBinding::new(KeyCode::Char('a')),
+            toggle_subtasks: KeyBinding::new(KeyCode::Tab),
+            edit_task: KeyBinding::new(KeyCode::Char('e')),
+            save_changes: KeyBinding::new(KeyCode::Enter),
+            enter_insert_mode: KeyBinding::new(KeyCode::Char('i')),
+            enter_normal_mode: KeyBinding::new(KeyCode::Esc),
+            enter_visual_mode: KeyBinding::new(KeyCode::Char('v')),
+            enter_command_mode: KeyBinding::new(KeyCode::Char(':')),
+            go_back: KeyBinding::new(KeyCode::Esc),
+            open_link: KeyBinding::new(KeyCode::Enter),
+            next_group: KeyBinding::new(KeyCode::Right),
+            prev_group: KeyBinding::new(KeyCode::Left),
+            switch_workspace: KeyBinding::new(KeyCode::Char('w')),
+            undo: KeyBinding::new(KeyCode::Char('u')),
+            redo: KeyBinding::ctrl(KeyCode::Char('r')),
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
 pub struct DateFormats {
     pub display_date_format: String,
     pub display_datetime_format: String,
@@ -318,8 +436,8 @@ pub struct DateFormats {
     pub input_datetime_hint: String,
 }
 
-impl DateFormats {
-    fn new() -> Self {
+impl Default for DateFormats {
+    fn default() -> Self {
         DateFormats {
             display_date_format: "%a %b %-d".to_string(),
             display_datetime_format: "%a %b %-d at %-H:%M".to_string(),
@@ -331,7 +449,8 @@ impl DateFormats {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
 pub struct Colors {
     #[serde(
         serialize_with = "serialize_color",
@@ -388,9 +507,54 @@ pub struct Colors {
         deserialize_with = "deserialize_color"
     )]
     pub command_mode_color: tui::style::Color,
+    #[serde(
+        serialize_with = "serialize_color",
+        deserialize_with = "deserialize_color"
+    )]
+    pub low_priority_color: tui::style::Color,
+    #[serde(
+        serialize_with = "serialize_color",
+        deserialize_with = "deserialize_color"
+    )]
+    pub medium_priority_color: tui::style::Color,
+    #[serde(
+        serialize_with = "serialize_color",
+        deserialize_with = "deserialize_color"
+    )]
+    pub high_priority_color: tui::style::Color,
+    #[serde(
+        serialize_with = "serialize_color",
+        deserialize_with = "deserialize_color"
+    )]
+    pub urgent_priority_color: tui::style::Color,
+    /// The background colors of tags, picked by name so a tag always has the same one
+    pub tag_colors: Vec<Color>,
 }
 
 impl Colors {
+    /// The color of the priority icon, for tasks that have a priority
+    pub fn priority_color(&self, priority: Priority) -> Option<tui::style::Color> {
+        match priority {
+            Priority::None => None,
+            Priority::Low => Some(self.low_priority_color),
+            Priority::Medium => Some(self.medium_priority_color),
+            Priority::High => Some(self.high_priority_color),
+            Priority::Urgent => Some(self.urgent_priority_color),
+        }
+    }
+
+    pub fn tag_color(&self, tag: &str) -> tui::style::Color {
+        let hash = tag.to_lowercase().bytes().fold(0usize, |hash, b| {
+            hash.wrapping_mul(31).wrapping_add(b as usize)
+        });
+        match self.tag_colors.len() {
+            0 => self.accent_color,
+            len => self.tag_colors[hash % len].to_tui_color(),
+        }
+    }
+}
+
+impl Default for Colors {
     fn default() -> Self {
         Colors {
             primary_color: tui::style::Color::Red,
@@ -404,101 +568,330 @@ impl Colors {
             insert_mode_color: tui::style::Color::LightGreen,
             visual_mode_color: tui::style::Color::LightBlue,
             command_mode_color: tui::style::Color::LightRed,
+            low_priority_color: tui::style::Color::Blue,
+            medium_priority_color: tui::style::Color::Yellow,
+            high_priority_color: tui::style::Color::LightRed,
+            urgent_priority_color: tui::style::Color::Red,
+            tag_colors: vec![
+                Color::LightBlue,
+                Color::LightMagenta,
+                Color::LightCyan,
+                Color::LightGreen,
+                Color::LightYellow,
+            ],
         }
     }
 }
 
+/// The view state kept separately for every workspace
 #[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct Settings {
-    pub date_formats: DateFormats,
+#[serde(default)]
+pub struct WorkspaceState {
     pub show_complete: bool,
+    /// Whether tasks that haven't reached their start date are shown
+    pub show_deferred: bool,
     pub current_group: Option<String>,
+    /// Only the tasks with this tag are shown
+    pub current_tag: Option<String>,
+}
+
+impl Default for WorkspaceState {
+    fn default() -> Self {
+        WorkspaceState {
+            show_complete: true,
+            show_deferred: false,
+            current_group: None,
+            current_tag: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub date_formats: DateFormats,
+    pub workspace: String,
+    pub workspaces: BTreeMap<String, WorkspaceState>,
     pub icons: Icons,
     pub colors: Colors,
     pub keybindings: KeyBindings,
+    pub backup_count: usize,
+    pub storage: Storage,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        SettingsBuilder::default().build()
+    }
 }
 
 impl Settings {
-    pub fn set_show_complete(&mut self, show_complete: bool) {
-        self.show_complete = show_complete;
-        self.save_state();
+    /// The state of the active workspace
+    pub fn workspace_state(&self) -> WorkspaceState {
+        self.workspaces
+            .get(&self.workspace)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn workspace_state_mut(&mut self) -> &mut WorkspaceState {
+        self.workspaces.entry(self.workspace.clone()).or_default()
     }
 
-    pub fn set_current_group(&mut self, group: Option<String>) {
-        self.current_group = group;
-        self.save_state();
+    pub fn show_complete(&self) -> bool {
+        self.workspace_state().show_complete
     }
 
-    pub fn set_vi_mode(&mut self) {
+    pub fn show_deferred(&self) -> bool {
+        self.workspace_state().show_deferred
+    }
+
+    pub fn current_group(&self) -> Option<String> {
+        self.workspace_state().current_group
+    }
+
+    pub fn set_show_complete(&mut self, show_complete: bool) -> Result<()> {
+        self.workspace_state_mut().show_complete = show_complete;
+        self.save_state()
+    }
+
+    pub fn current_tag(&self) -> Option<String> {
+        self.workspace_state().current_tag
+    }
+
+    pub fn set_current_tag(&mut self, tag: Option<String>) -> Result<()> {
+        self.workspace_state_mut().current_tag = tag;
+        self.save_state()
+    }
+
+    pub fn set_show_deferred(&mut self, show_deferred: bool) -> Result<()> {
+        self.workspace_state_mut().show_deferred = show_deferred;
+        self.save_state()
+    }
+
+    pub fn set_current_group(&mut self, group: Option<String>) -> Result<()> {
+        self.workspace_state_mut().current_group = group;
+        self.save_state()
+    }
+
+    pub fn set_workspace(&mut self, workspace: &str) -> Result<()> {
+        self.workspace = workspace.to_string();
+        self.save_state()
+    }
+
+    pub fn remove_workspace_state(&mut self, workspace: &str) -> Result<()> {
+        self.workspaces.remove(workspace);
+        self.save_state()
+    }
+
+    pub fn set_vi_mode(&mut self) -> Result<()> {
         self.keybindings = KeyBindings::get_vi_default();
         self.save_state()
     }
 
-    pub fn set_normal_mode(&mut self) {
+    pub fn set_normal_mode(&mut self) -> Result<()> {
         self.keybindings = KeyBindings::default();
         self.save_state()
     }
 
-    pub fn set_special_icons(&mut self) {
+    pub fn set_special_icons(&mut self) -> Result<()> {
         self.icons = Icons::special();
         self.save_state()
     }
 
-    pub fn set_char_icons(&mut self) {
+    pub fn set_char_icons(&mut self) -> Result<()> {
         self.icons = Icons::default();
         self.save_state()
     }
 
-    pub fn save_state(&self) {
-        let settings_path =
-            SettingsBuilder::get_settings_path().expect("Settings file should exist.");
-        utils::save_settings(&settings_path, self);
+    pub fn set_storage(&mut self, storage: Storage) -> Result<()> {
+        self.storage = storage;
+        self.save_state()
+    }
+
+    /// Takes the settings that can change while the app is running from `loaded`. The active
+    /// workspace, its view state and the storage are kept, since changing them means reopening
+    /// the task store. Returns whether anything visible changed.
+    pub fn reload_from(&mut self, mut loaded: Settings) -> bool {
+        let changed = self.date_formats != loaded.date_formats
+            || self.icons != loaded.icons
+            || self.colors != loaded.colors
+            || self.keybindings != loaded.keybindings;
+        self.date_formats = loaded.date_formats;
+        self.icons = loaded.icons;
+        self.colors = loaded.colors;
+        self.keybindings = loaded.keybindings;
+        self.backup_count = loaded.backup_count;
+
+        // Keep the view state of other workspaces so it isn't lost the next time we save
+        loaded
+            .workspaces
+            .insert(self.workspace.clone(), self.workspace_state());
+        self.workspaces = loaded.workspaces;
+        changed
+    }
+
+    pub fn save_state(&self) -> Result<()> {
+        let settings_path = SettingsBuilder::get_settings_path()?;
+        utils::save_settings(&settings_path, self)
     }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(default)]
 pub struct SettingsBuilder {
     pub date_formats: DateFormats,
-    pub show_complete: bool,
-    pub current_group: Option<String>,
+    pub workspace: String,
+    pub workspaces: BTreeMap<String, WorkspaceState>,
     pub icons: Icons,
     pub colors: Colors,
     pub keybindings: KeyBindings,
+    pub backup_count: usize,
+    pub storage: Storage,
+}
+
+/// Locations given on the command line. They take precedence over the environment variables.
+#[derive(Default, Debug)]
+pub struct PathOverrides {
+    pub config_dir: Option<PathBuf>,
+    pub db: Option<PathBuf>,
+}
+
+static PATH_OVERRIDES: OnceLock<PathOverrides> = OnceLock::new();
+
+/// Sets the locations given on the command line. Only the first call has any effect.
+pub fn set_path_overrides(overrides: PathOverrides) {
+    let _ = PATH_OVERRIDES.set(overrides);
+}
+
+fn env_path(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The database file given with `--db` or `GYST_DB`, if any
+fn db_override() -> Option<PathBuf> {
+    PATH_OVERRIDES
+        .get()
+        .and_then(|o| o.db.clone())
+        .or_else(|| env_path("GYST_DB"))
+}
+
+/// Whether a database was given with `--db` or `GYST_DB`
+pub fn has_db_override() -> bool {
+    db_override().is_some()
+}
+
+/// Follows the XDG base directory spec, which only allows absolute paths
+fn xdg_dir(var: &str, fallback: &[&str]) -> Result<PathBuf> {
+    if let Some(dir) = env_path(var).filter(|d| d.is_absolute()) {
+        return Ok(dir.join("gyst-tui"));
+    }
+    match dirs::home_dir() {
+        Some(home) => Ok(fallback
+            .iter()
+            .fold(home, |path, part| path.join(part))
+            .join("gyst-tui")),
+        None => Err(anyhow!("Could not find home directory")),
+    }
 }
 
 impl SettingsBuilder {
+    /// The directory holding `settings.json`
     pub fn default_path() -> Result<PathBuf> {
-        match dirs::home_dir() {
-            Some(path) => {
-                let path = Path::new(&path);
+        let config_dir = match PATH_OVERRIDES.get().and_then(|o| o.config_dir.clone()) {
+            Some(dir) => dir,
+            None => match env_path("GYST_CONFIG_DIR") {
+                Some(dir) => dir,
+                None => xdg_dir("XDG_CONFIG_HOME", &[".config"])?,
+            },
+        };
+        if !config_dir.exists() {
+            fs::create_dir_all(&config_dir)
+                .with_context(|| format!("Unable to create {}", config_dir.display()))?;
+        }
+        Ok(config_dir)
+    }
 
-                let config_dir = path.join(".config");
-                if !path.exists() {
-                    fs::create_dir_all(path)?;
-                }
+    /// The directory holding the task database and its backups
+    pub fn data_path() -> Result<PathBuf> {
+        let data_dir = xdg_dir("XDG_DATA_HOME", &[".local", "share"])?;
+        if !data_dir.exists() {
+            fs::create_dir_all(&data_dir)
+                .with_context(|| format!("Unable to create {}", data_dir.display()))?;
+        }
+        Ok(data_dir)
+    }
 
-                let todo_dir = config_dir.join("gyst-tui");
-                if !todo_dir.exists() {
-                    fs::create_dir_all(&todo_dir)?;
-                }
-                Ok(todo_dir)
+    /// Older versions kept the tasks next to the settings. Moves them to the data directory.
+    fn move_legacy_data(data_dir: &Path, storage: Storage) -> Result<()> {
+        let legacy_dir = Self::default_path()?;
+        if legacy_dir == data_dir {
+            return Ok(());
+        }
+
+        for name in [storage.file_name(), "backups"] {
+            let old_path = legacy_dir.join(name);
+            let new_path = data_dir.join(name);
+            if old_path.exists() && !new_path.exists() {
+                fs::rename(&old_path, &new_path).with_context(|| {
+                    format!(
+                        "Unable to move {} to {}",
+                        old_path.display(),
+                        new_path.display()
+                    )
+                })?;
             }
-            None => Err(anyhow!("Could not find home directory")),
         }
+        Ok(())
     }
 
-    pub fn get_default_db_file() -> Result<PathBuf> {
-        let default_path = Self::default_path()?;
-        let path = default_path.join("tasks.json");
+    /// The directory holding the tasks of a workspace. The default workspace lives directly
+    /// in the data directory.
+    pub fn workspace_path(workspace: &str) -> Result<PathBuf> {
+        let data_dir = Self::data_path()?;
+        if workspace == DEFAULT_WORKSPACE {
+            Ok(data_dir)
+        } else {
+            Ok(data_dir.join("workspaces").join(workspace))
+        }
+    }
 
-        if !path.exists() {
-            let mut file = OpenOptions::new().write(true).create(true).open(&path)?;
-            writeln!(file, "{{}}")?;
+    pub fn get_default_db_file(workspace: &str, storage: Storage) -> Result<PathBuf> {
+        let path = match db_override() {
+            // Used exactly as given, whatever its extension
+            Some(path) => path,
+            None => {
+                let workspace_dir = Self::workspace_path(workspace)?;
+                if workspace == DEFAULT_WORKSPACE {
+                    Self::move_legacy_data(&workspace_dir, storage)?;
+                }
+                workspace_dir.join(storage.file_name())
+            }
+        };
+
+        // SQLite creates its own file
+        if storage == Storage::Json && !path.exists() {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Unable to create {}", parent.display()))?;
+            }
+            utils::atomic_write(&path, &utils::serialize_tasks(&HashMap::new())?)?;
         }
 
         Ok(path)
     }
 
+    /// Backups of a database given with `--db` are kept next to it, e.g. in `work.json.backups`
+    /// for `work.json`, so that separate task lists don't share them
+    pub fn get_backup_dir(workspace: &str) -> Result<PathBuf> {
+        match db_override() {
+            Some(path) => Ok(utils::append_to_file_name(&path, ".backups")),
+            None => Ok(Self::workspace_path(workspace)?.join("backups")),
+        }
+    }
+
     pub fn get_settings_path() -> Result<PathBuf> {
         let default_path = Self::default_path()?;
         let path = default_path.join("settings.json");
@@ -512,18 +905,20 @@ impl SettingsBuilder {
         let default_path = Self::default_path()?;
         let path = default_path.join("settings.json");
         let settings_json = serde_json::to_string_pretty(&self)?;
-        fs::write(&path, settings_json)?;
+        utils::atomic_write(&path, settings_json.as_bytes())?;
         Ok(())
     }
 
     pub fn build(&mut self) -> Settings {
         Settings {
             date_formats: self.date_formats.clone(),
-            show_complete: self.show_complete,
-            current_group: self.current_group.clone(),
+            workspace: self.workspace.clone(),
+            workspaces: self.workspaces.clone(),
             icons: self.icons.clone(),
             colors: self.colors.clone(),
             keybindings: self.keybindings.clone(),
+            backup_count: self.backup_count,
+            storage: self.storage,
         }
     }
 }
@@ -531,25 +926,105 @@ impl SettingsBuilder {
 impl Default for SettingsBuilder {
     fn default() -> Self {
         SettingsBuilder {
-            show_complete: true,
-            current_group: None,
+            workspace: DEFAULT_WORKSPACE.to_string(),
+            workspaces: BTreeMap::new(),
             icons: Icons::default(),
-            date_formats: DateFormats::new(),
+            date_formats: DateFormats::default(),
             colors: Colors::default(),
             keybindings: KeyBindings::default(),
+            backup_count: 10,
+            storage: Storage::default(),
         }
     }
 }
 
-pub fn get_configuration() -> Settings {
-    let settings_path = SettingsBuilder::get_settings_path().expect("Settings file should exist.");
-    let file = OpenOptions::new()
-        .read(true)
-        .open(settings_path)
-        .expect("Could not open settings file");
-    serde_json::from_reader(file).expect("Could not parse settings file")
+/// Fields whose keys are chosen by the user
+const FREE_FORM_FIELDS: &[&str] = &["workspaces"];
+
+/// Returns the dotted paths of the fields in `value` that don't exist in `known`
+fn unknown_fields(value: &Value, known: &Value, prefix: &str) -> Vec<String> {
+    let (Value::Object(fields), Value::Object(known_fields)) = (value, known) else {
+        return vec![];
+    };
+
+    let mut unknown = vec![];
+    for (key, field) in fields {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match known_fields.get(key) {
+            _ if FREE_FORM_FIELDS.contains(&path.as_str()) => {}
+            Some(known_field) => unknown.append(&mut unknown_fields(field, known_field, &path)),
+            None => unknown.push(path),
+        }
+    }
+    unknown
+}
+
+/// Settings from before workspaces kept `show_complete` and `current_group` at the top level.
+/// Moves them to the default workspace, returning whether anything changed.
+fn move_legacy_view_state(value: &mut Value) -> bool {
+    let Value::Object(fields) = value else {
+        return false;
+    };
+
+    let mut state = serde_json::Map::new();
+    for key in ["show_complete", "current_group"] {
+        if let Some(field) = fields.remove(key) {
+            state.insert(key.to_string(), field);
+        }
+    }
+    if state.is_empty() {
+        return false;
+    }
+
+    let workspaces = fields
+        .entry("workspaces")
+        .or_insert_with(|| Value::Object(serde_json::Map::new()));
+    if let Value::Object(workspaces) = workspaces {
+        workspaces
+            .entry(DEFAULT_WORKSPACE)
+            .or_insert(Value::Object(state));
+    }
+    true
+}
+
+/// Reads the settings file. Missing fields are filled in with their defaults, and the names
+/// of the fields that are not recognized are returned alongside the settings.
+pub fn load_settings(path: &Path) -> Result<(Settings, Vec<String>), LoadError> {
+    let contents = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+    let mut value: Value =
+        serde_json::from_str(&contents).map_err(|e| LoadError::parse(path, e))?;
+    let settings: Settings = if move_legacy_view_state(&mut value) {
+        serde_json::from_value(value.clone()).map_err(|e| LoadError::schema(path, e))?
+    } else {
+        serde_json::from_str(&contents).map_err(|e| LoadError::parse(path, e))?
+    };
+
+    let known = serde_json::to_value(Settings::default()).expect("Settings are serializable");
+    let unknown = unknown_fields(&value, &known, "");
+    Ok((settings, unknown))
+}
+
+pub fn get_configuration() -> Result<Settings, LoadError> {
+    let settings_path = SettingsBuilder::get_settings_path().map_err(LoadError::location)?;
+    let (settings, unknown) = load_settings(&settings_path)?;
+    for field in unknown {
+        eprintln!(
+            "Warning: unknown setting '{}' in {}",
+            field,
+            settings_path.display()
+        );
+    }
+    Ok(settings)
+}
+
+pub fn get_db_file(workspace: &str, storage: Storage) -> Result<PathBuf, LoadError> {
+    SettingsBuilder::get_default_db_file(workspace, storage).map_err(LoadError::location)
 }
 
-pub fn get_db_file() -> PathBuf {
-    SettingsBuilder::get_default_db_file().expect("Could not find default db file")
+pub fn get_backup_dir(workspace: &str) -> Result<PathBuf, LoadError> {
+    SettingsBuilder::get_backup_dir(workspace).map_err(LoadError::location)
 }
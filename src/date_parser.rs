@@ -0,0 +1,191 @@
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+use chrono::{
+    DateTime, Datelike, Days, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    Timelike, Utc,
+};
+use chrono_tz::Tz;
+
+use crate::{
+    day_of_week::DayOfWeek,
+    due::{self, Due},
+};
+
+/// Parses a date relative to `now` like "today", "tomorrow", "next fri", "in 3 days",
+/// "mon 9am", "eod" or "end of month", or an ISO 8601 date like "2026-10-16" or
+/// "2026-10-16T09:00". Dates without a time are due at any time of the day. Times are in the
+/// time zone of this machine, unless followed by another one like "mon 9am America/New_York".
+pub fn parse(input: &str, now: DateTime<Local>) -> Result<Due> {
+    let (input, zone) = split_zone(input);
+    let zone = zone.or_else(due::local_zone);
+    let input = input.trim().to_lowercase();
+    if let Some(due) = parse_iso(&input.to_uppercase(), zone) {
+        return Ok(due);
+    }
+
+    let mut words: Vec<&str> = input.split_whitespace().collect();
+    let time = match words.last().and_then(|w| parse_time(w)) {
+        Some(time) => {
+            words.pop();
+            if words.last() == Some(&"at") {
+                words.pop();
+            }
+            Some(time)
+        }
+        None => None,
+    };
+
+    let today = now.date_naive();
+    let now = now
+        .with_second(0)
+        .and_then(|now| now.with_nanosecond(0))
+        .unwrap_or(now);
+    let day = match words.as_slice() {
+        [] if time.is_some() => today,
+        ["today" | "tod" | "eod"] => today,
+        ["tomorrow" | "tom"] => today + Days::new(1),
+        ["yesterday"] => today - Days::new(1),
+        ["eow"] | ["end", "of", "week"] => {
+            today + Days::new(6 - today.weekday().num_days_from_monday() as u64)
+        }
+        ["eom"] | ["end", "of", "month"] => last_day_of_month(today),
+        ["eoy"] | ["end", "of", "year"] => NaiveDate::from_ymd_opt(today.year(), 12, 31).unwrap(),
+        ["next", "week"] => today + Days::new(7),
+        ["next", "month"] => add_months(today, 1)?,
+        ["next", "year"] => add_months(today, 12)?,
+        ["next", day] | [day] => next_weekday(today, day)?,
+        ["in", amount, unit] => {
+            let amount = parse_amount(amount)?;
+            match unit.trim_end_matches('s') {
+                "minute" | "min" if time.is_none() => {
+                    let at = add_duration(now, Duration::minutes(amount))?;
+                    return Ok(Due::DateTime { at, zone });
+                }
+                "hour" | "hr" if time.is_none() => {
+                    let at = add_duration(now, Duration::hours(amount))?;
+                    return Ok(Due::DateTime { at, zone });
+                }
+                "day" => add_days(today, amount as u64)?,
+                "week" => add_days(
+                    today,
+                    (amount as u64).checked_mul(7).ok_or_else(out_of_range)?,
+                )?,
+                "month" => add_months(today, amount as u32)?,
+                "year" => add_months(
+                    today,
+                    (amount as u32).checked_mul(12).ok_or_else(out_of_range)?,
+                )?,
+                _ => return Err(anyhow!("Unknown unit '{}'", unit)),
+            }
+        }
+        _ => return Err(anyhow!("Unable to parse date '{}'", input)),
+    };
+
+    match time {
+        Some(time) => Due::from_local(day.and_time(time), zone)
+            .ok_or_else(|| anyhow!("Date '{}' is out of range", input)),
+        None => Ok(Due::Date(day)),
+    }
+}
+
+/// "2026-10-16", "2026-10-16T09:00", "2026-10-16 09:00:00" or with a UTC offset like
+/// "2026-10-16T09:00:00+02:00"
+fn parse_iso(s: &str, zone: Option<Tz>) -> Option<Due> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        let at = datetime.with_timezone(&Utc);
+        return Some(Due::DateTime { at, zone });
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Some(Due::Date(date));
+    }
+    let datetime = [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())?;
+    Due::from_local(datetime, zone)
+}
+
+/// Splits off a trailing IANA time zone like "Europe/Berlin" or "UTC"
+pub fn split_zone(input: &str) -> (&str, Option<Tz>) {
+    let input = input.trim();
+    match input.rsplit_once(char::is_whitespace) {
+        Some((rest, zone)) => match Tz::from_str(zone) {
+            Ok(zone) => (rest, Some(zone)),
+            Err(_) => (input, None),
+        },
+        None => (input, None),
+    }
+}
+
+/// "9am", "9:30pm", "14:30", "noon" or "midnight"
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    match s {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+    let (s, offset) = if let Some(s) = s.strip_suffix("am") {
+        (s, Some(0))
+    } else if let Some(s) = s.strip_suffix("pm") {
+        (s, Some(12))
+    } else {
+        (s, None)
+    };
+    let (hour, minute) = match s.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse().ok()?),
+        // A bare number is a count, e.g. in "in 3 days", unless it says am or pm
+        None if offset.is_some() => (s.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+    let hour = match offset {
+        Some(offset) if (1..=12).contains(&hour) => hour % 12 + offset,
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// The first day after `today` that falls on `day`, e.g. "fri"
+fn next_weekday(today: NaiveDate, day: &str) -> Result<NaiveDate> {
+    let weekday = DayOfWeek::from_str(day)
+        .map_err(|_| anyhow!("Unable to parse date '{}'", day))?
+        .to_chrono();
+    let days = (weekday.num_days_from_monday() + 6 - today.weekday().num_days_from_monday()) % 7;
+    Ok(today + Days::new(days as u64 + 1))
+}
+
+fn last_day_of_month(day: NaiveDate) -> NaiveDate {
+    let first = day.with_day(1).unwrap();
+    first + Months::new(1) - Days::new(1)
+}
+
+fn out_of_range() -> anyhow::Error {
+    anyhow!("Date is out of range")
+}
+
+fn add_duration(now: DateTime<Local>, duration: Duration) -> Result<DateTime<Utc>> {
+    now.checked_add_signed(duration)
+        .map(|at| at.with_timezone(&Utc))
+        .ok_or_else(out_of_range)
+}
+
+fn add_days(day: NaiveDate, days: u64) -> Result<NaiveDate> {
+    day.checked_add_days(Days::new(days))
+        .ok_or_else(out_of_range)
+}
+
+fn add_months(day: NaiveDate, months: u32) -> Result<NaiveDate> {
+    day.checked_add_months(Months::new(months))
+        .ok_or_else(out_of_range)
+}
+
+fn parse_amount(s: &str) -> Result<i64> {
+    s.parse::<u32>()
+        .map(i64::from)
+        .map_err(|_| anyhow!("Invalid number '{}'", s))
+}
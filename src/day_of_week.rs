@@ -5,7 +5,7 @@ use std::{
     str::FromStr,
 };
 
-#[derive(Serialize, Deserialize, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub enum DayOfWeek {
     Monday,
     Tuesday,
@@ -17,6 +17,17 @@ pub enum DayOfWeek {
 }
 
 impl DayOfWeek {
+    /// Monday to Friday
+    pub const WEEKDAYS: [DayOfWeek; 5] = [
+        DayOfWeek::Monday,
+        DayOfWeek::Tuesday,
+        DayOfWeek::Wednesday,
+        DayOfWeek::Thursday,
+        DayOfWeek::Friday,
+    ];
+
+    pub const WEEKEND: [DayOfWeek; 2] = [DayOfWeek::Saturday, DayOfWeek::Sunday];
+
     pub fn to_int(&self) -> u32 {
         match self {
             DayOfWeek::Monday => 1,
@@ -40,6 +51,18 @@ impl DayOfWeek {
             chrono::Weekday::Sun => Self::Sunday,
         }
     }
+
+    pub fn to_chrono(&self) -> chrono::Weekday {
+        match self {
+            Self::Monday => chrono::Weekday::Mon,
+            Self::Tuesday => chrono::Weekday::Tue,
+            Self::Wednesday => chrono::Weekday::Wed,
+            Self::Thursday => chrono::Weekday::Thu,
+            Self::Friday => chrono::Weekday::Fri,
+            Self::Saturday => chrono::Weekday::Sat,
+            Self::Sunday => chrono::Weekday::Sun,
+        }
+    }
 }
 
 impl FromStr for DayOfWeek {
@@ -47,13 +70,13 @@ impl FromStr for DayOfWeek {
 
     fn from_str(s: &str) -> Result<DayOfWeek, Self::Err> {
         match s.to_lowercase().as_str() {
-            "mon" => Ok(DayOfWeek::Monday),
-            "tue" => Ok(DayOfWeek::Tuesday),
-            "wed" => Ok(DayOfWeek::Wednesday),
-            "thu" => Ok(DayOfWeek::Thursday),
-            "fri" => Ok(DayOfWeek::Friday),
-            "sat" => Ok(DayOfWeek::Saturday),
-            "sun" => Ok(DayOfWeek::Sunday),
+            "mon" | "monday" => Ok(DayOfWeek::Monday),
+            "tue" | "tuesday" => Ok(DayOfWeek::Tuesday),
+            "wed" | "wednesday" => Ok(DayOfWeek::Wednesday),
+            "thu" | "thursday" => Ok(DayOfWeek::Thursday),
+            "fri" | "friday" => Ok(DayOfWeek::Friday),
+            "sat" | "saturday" => Ok(DayOfWeek::Saturday),
+            "sun" | "sunday" => Ok(DayOfWeek::Sunday),
             _ => Err(anyhow::anyhow!("Invalid day of the week")),
         }
     }
@@ -0,0 +1,166 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use chrono::{
+    DateTime, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// When a task is due, either on a day or at a specific time
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Due {
+    /// Due at any time of the day, wherever the viewer is
+    Date(NaiveDate),
+    /// Due at an instant. The time zone, if known, is the one the time of day was given in, so
+    /// that repeating tasks stay at that time of day across daylight saving time changes.
+    /// Without one, the viewer's time zone is used.
+    DateTime { at: DateTime<Utc>, zone: Option<Tz> },
+}
+
+/// The IANA time zone of this machine, e.g. `Europe/Berlin`, if it can be found
+pub fn local_zone() -> Option<Tz> {
+    let name = match std::env::var("TZ") {
+        Ok(tz) if !tz.is_empty() => tz.trim_start_matches(':').to_string(),
+        _ => iana_time_zone::get_timezone().ok()?,
+    };
+    Tz::from_str(&name).ok()
+}
+
+/// The instant a wall clock time happens at in `zone`. When the clocks go back, it's the first
+/// of the two. When they go forward, times that are skipped happen as much later, e.g. 02:30
+/// becomes 03:30.
+fn resolve<Z: TimeZone>(zone: &Z, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    match zone.from_local_datetime(&naive) {
+        LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => Some(at.with_timezone(&Utc)),
+        LocalResult::None => zone
+            .from_local_datetime(&(naive + Duration::hours(1)))
+            .earliest()
+            .map(|at| at.with_timezone(&Utc)),
+    }
+}
+
+impl Due {
+    /// Due at `datetime`, keeping to the time zone of this machine
+    pub fn at(datetime: DateTime<Local>) -> Due {
+        Due::DateTime {
+            at: datetime.with_timezone(&Utc),
+            zone: local_zone(),
+        }
+    }
+
+    /// Due at a wall clock time in `zone`, or in the viewer's time zone without one
+    pub fn from_local(naive: NaiveDateTime, zone: Option<Tz>) -> Option<Due> {
+        let at = match zone {
+            Some(zone) => resolve(&zone, naive)?,
+            None => resolve(&Local, naive)?,
+        };
+        Some(Due::DateTime { at, zone })
+    }
+
+    /// The instant the task is due in the viewer's time zone, for tasks due at a time
+    pub fn to_local(&self) -> Option<DateTime<Local>> {
+        match self {
+            Due::Date(_) => None,
+            Due::DateTime { at, .. } => Some(at.with_timezone(&Local)),
+        }
+    }
+
+    /// The day the task is due on for the viewer
+    pub fn date_naive(&self) -> NaiveDate {
+        match self {
+            Due::Date(date) => *date,
+            Due::DateTime { at, .. } => at.with_timezone(&Local).date_naive(),
+        }
+    }
+
+    /// The time of day for the viewer, for tasks that have one
+    pub fn time(&self) -> Option<NaiveTime> {
+        self.to_local().map(|datetime| datetime.time())
+    }
+
+    pub fn has_time(&self) -> bool {
+        self.time().is_some()
+    }
+
+    /// Whether `now` is on that day or past that time
+    pub fn is_reached(&self, now: DateTime<Local>) -> bool {
+        match self {
+            Due::Date(date) => *date <= now.date_naive(),
+            Due::DateTime { at, .. } => *at <= now.with_timezone(&Utc),
+        }
+    }
+
+    /// The last moment the task isn't overdue, which is the end of the day for tasks due on a day
+    pub fn deadline(&self) -> DateTime<Local> {
+        match self {
+            Due::Date(date) => {
+                let end_of_day = date.and_hms_opt(23, 59, 59).unwrap();
+                Local
+                    .from_local_datetime(&end_of_day)
+                    .latest()
+                    .unwrap_or_else(|| Local.from_utc_datetime(&end_of_day))
+            }
+            Due::DateTime { at, .. } => at.with_timezone(&Local),
+        }
+    }
+
+    /// The day and time on the wall clock of the task's own time zone
+    fn wall_clock(&self) -> NaiveDateTime {
+        match self {
+            Due::Date(date) => date.and_time(NaiveTime::MIN),
+            Due::DateTime {
+                at,
+                zone: Some(zone),
+            } => at.with_timezone(zone).naive_local(),
+            Due::DateTime { at, zone: None } => at.with_timezone(&Local).naive_local(),
+        }
+    }
+
+    /// The day the task is due on in its own time zone, which is what repeats count from
+    pub fn zoned_date(&self) -> NaiveDate {
+        self.wall_clock().date()
+    }
+
+    /// The same kind of due on another day, at the same time of day in the task's time zone if
+    /// it has one
+    pub fn on(&self, date: NaiveDate) -> Option<Due> {
+        match self {
+            Due::Date(_) => Some(Due::Date(date)),
+            Due::DateTime { zone, .. } => {
+                Due::from_local(date.and_time(self.wall_clock().time()), *zone)
+            }
+        }
+    }
+}
+
+impl Ord for Due {
+    /// By day, with the tasks due at any time of the day after the ones due at a time
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.date_naive()
+            .cmp(&other.date_naive())
+            .then_with(|| match (self, other) {
+                (Due::Date(_), Due::Date(_)) => Ordering::Equal,
+                (Due::Date(_), Due::DateTime { .. }) => Ordering::Greater,
+                (Due::DateTime { .. }, Due::Date(_)) => Ordering::Less,
+                (
+                    Due::DateTime {
+                        at: a,
+                        zone: a_zone,
+                    },
+                    Due::DateTime {
+                        at: b,
+                        zone: b_zone,
+                    },
+                ) => a
+                    .cmp(b)
+                    .then_with(|| a_zone.map(|z| z.name()).cmp(&b_zone.map(|z| z.name()))),
+            })
+    }
+}
+
+impl PartialOrd for Due {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::utils::SCHEMA_VERSION;
+
+/// Errors that can happen while reading the task database or the settings file.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The directory the file belongs in can't be found or created
+    Location(String),
+    Missing(PathBuf),
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Schema {
+        path: PathBuf,
+        message: String,
+    },
+    /// Written by a newer gyst-tui, so there's nothing wrong with it
+    Newer {
+        path: PathBuf,
+        version: u32,
+    },
+    Storage {
+        path: PathBuf,
+        message: String,
+    },
+}
+
+impl LoadError {
+    pub fn io(path: &Path, source: io::Error) -> Self {
+        if source.kind() == io::ErrorKind::NotFound {
+            LoadError::Missing(path.to_path_buf())
+        } else {
+            LoadError::Io {
+                path: path.to_path_buf(),
+                source,
+            }
+        }
+    }
+
+    pub fn parse(path: &Path, source: serde_json::Error) -> Self {
+        // serde_json appends the position to its messages, we print it ourselves
+        let message = source.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(idx) => message[..idx].to_string(),
+            None => message,
+        };
+        LoadError::Parse {
+            path: path.to_path_buf(),
+            line: source.line(),
+            column: source.column(),
+            message,
+        }
+    }
+
+    pub fn location(source: impl Display) -> Self {
+        LoadError::Location(format!("{:#}", source))
+    }
+
+    pub fn schema(path: &Path, source: impl Display) -> Self {
+        LoadError::Schema {
+            path: path.to_path_buf(),
+            message: format!("{:#}", source),
+        }
+    }
+
+    pub fn storage(path: &Path, source: impl Display) -> Self {
+        LoadError::Storage {
+            path: path.to_path_buf(),
+            message: source.to_string(),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            LoadError::Location(_) => None,
+            LoadError::Missing(path) => Some(path),
+            LoadError::Io { path, .. } => Some(path),
+            LoadError::Parse { path, .. } => Some(path),
+            LoadError::Schema { path, .. } => Some(path),
+            LoadError::Newer { path, .. } => Some(path),
+            LoadError::Storage { path, .. } => Some(path),
+        }
+    }
+
+    /// Whether the file exists but its contents can't be used
+    pub fn is_corrupt(&self) -> bool {
+        matches!(self, LoadError::Parse { .. } | LoadError::Schema { .. })
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Location(message) => write!(f, "{}", message),
+            LoadError::Missing(path) => write!(f, "{} does not exist", path.display()),
+            LoadError::Io { path, source } => {
+                write!(f, "Unable to read {}: {}", path.display(), source)
+            }
+            LoadError::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Unable to parse {} (line {}, column {}): {}",
+                path.display(),
+                line,
+                column,
+                message
+            ),
+            LoadError::Schema { path, message } => {
+                write!(f, "Unable to load {}: {}", path.display(), message)
+            }
+            LoadError::Newer { path, version } => write!(
+                f,
+                "Unable to load {}: schema version {} is newer than the supported version {}, \
+                 please upgrade gyst-tui",
+                path.display(),
+                version,
+                SCHEMA_VERSION
+            ),
+            LoadError::Storage { path, message } => {
+                write!(f, "Unable to open {}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
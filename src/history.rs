@@ -0,0 +1,215 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    error::LoadError,
+    store::TaskStore,
+    task::Task,
+    utils::{self, SCHEMA_VERSION},
+};
+
+/// How many actions are kept in the journal before the oldest ones are forgotten
+const MAX_ACTIONS: usize = 100;
+
+/// A change to a single task, with enough information to reverse it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum Change {
+    Insert(Task),
+    Delete(Task),
+    Update { before: Box<Task>, after: Box<Task> },
+}
+
+impl Change {
+    pub fn update(before: Task, after: Task) -> Change {
+        Change::Update {
+            before: Box::new(before),
+            after: Box::new(after),
+        }
+    }
+
+    pub fn inverse(&self) -> Change {
+        match self {
+            Change::Insert(task) => Change::Delete(task.clone()),
+            Change::Delete(task) => Change::Insert(task.clone()),
+            Change::Update { before, after } => Change::Update {
+                before: after.clone(),
+                after: before.clone(),
+            },
+        }
+    }
+
+    /// Stores `task` under its id, whether or not a task with that id exists
+    fn put(store: &mut dyn TaskStore, task: &Task) -> Result<()> {
+        if store.get(task.id.unwrap_or_default()).is_some() {
+            store.update(task.clone())
+        } else {
+            store.insert(task.clone()).map(|_| ())
+        }
+    }
+
+    /// Makes the store reflect this change. Tasks that were changed in the meantime are
+    /// overwritten, so that applying a change always ends in the same state.
+    fn apply(&self, store: &mut dyn TaskStore) -> Result<()> {
+        match self {
+            Change::Insert(task) => Self::put(store, task),
+            Change::Update { after, .. } => Self::put(store, after),
+            Change::Delete(task) => {
+                if let Some(id) = task.id {
+                    store.delete(id)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Everything changed by a single user action, e.g. editing a task
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Action {
+    pub description: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Journal {
+    /// The version of the task database the snapshots in the journal were taken at
+    schema_version: u32,
+    undo: Vec<Action>,
+    redo: Vec<Action>,
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Journal {
+            schema_version: SCHEMA_VERSION,
+            undo: vec![],
+            redo: vec![],
+        }
+    }
+}
+
+impl Journal {
+    /// Parses a journal of any known version, upgrading its task snapshots to `SCHEMA_VERSION`
+    fn parse(contents: &str) -> Result<Journal> {
+        let journal: Value = serde_json::from_str(contents)?;
+        let version = utils::schema_version(&journal)?;
+        Self::migrate(journal, version)
+    }
+
+    fn migrate(mut journal: Value, version: u32) -> Result<Journal> {
+        if version > SCHEMA_VERSION {
+            return Err(anyhow!(
+                "Schema version {} is newer than the supported version {}, please upgrade gyst-tui",
+                version,
+                SCHEMA_VERSION
+            ));
+        }
+        for stack in ["undo", "redo"] {
+            let actions = journal
+                .get_mut(stack)
+                .and_then(Value::as_array_mut)
+                .ok_or_else(|| anyhow!("Missing {} actions", stack))?;
+            for action in actions {
+                let changes = action
+                    .get_mut("changes")
+                    .and_then(Value::as_array_mut)
+                    .ok_or_else(|| anyhow!("Missing changes"))?;
+                for change in changes {
+                    for pointer in ["/Insert", "/Delete", "/Update/before", "/Update/after"] {
+                        if let Some(task) = change.pointer_mut(pointer) {
+                            *task = utils::migrate_task(task.take(), version)?;
+                        }
+                    }
+                }
+            }
+        }
+        journal["schema_version"] = json!(SCHEMA_VERSION);
+        Ok(serde_json::from_value(journal)?)
+    }
+}
+
+/// The undo/redo journal of a task database. It's kept in a file next to the database, and
+/// read again for every operation so that the CLI and a running TUI share the same history.
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    /// The journal for the database at `db`, e.g. `tasks.json.history` for `tasks.json`
+    pub fn for_db(db: &Path) -> History {
+        History {
+            path: utils::append_to_file_name(db, ".history"),
+        }
+    }
+
+    /// Reads the journal. One that can't be read is moved aside, so that a new one is started
+    /// after the error.
+    fn load(&self) -> Result<Journal, LoadError> {
+        if !self.path.exists() {
+            return Ok(Journal::default());
+        }
+        let contents = fs::read_to_string(&self.path).map_err(|e| LoadError::io(&self.path, e))?;
+        Journal::parse(&contents).map_err(|e| match utils::move_aside(&self.path) {
+            Ok(moved) => LoadError::schema(
+                &self.path,
+                format!("{:#}, moved it to {}", e, moved.display()),
+            ),
+            Err(_) => LoadError::schema(&self.path, e),
+        })
+    }
+
+    fn save(&self, journal: &Journal) -> Result<()> {
+        utils::atomic_write(&self.path, &serde_json::to_vec(journal)?)
+    }
+
+    /// Adds an action to the journal. Anything that was undone can no longer be redone.
+    pub fn record(&self, action: Action) -> Result<()> {
+        if action.changes.is_empty() {
+            return Ok(());
+        }
+        // The change is already saved, so it goes in a new journal when the old one can't be
+        // read. The old one is kept next to it, under the name in the error undo reports.
+        let mut journal = match self.load() {
+            Ok(journal) => journal,
+            Err(_) if !self.path.exists() => Journal::default(),
+            Err(e) => return Err(e.into()),
+        };
+        journal.undo.push(action);
+        let excess = journal.undo.len().saturating_sub(MAX_ACTIONS);
+        journal.undo.drain(..excess);
+        journal.redo.clear();
+        self.save(&journal)
+    }
+
+    /// Reverts the last action, returning it
+    pub fn undo(&self, store: &mut dyn TaskStore) -> Result<Option<Action>> {
+        let mut journal = self.load()?;
+        let Some(action) = journal.undo.pop() else {
+            return Ok(None);
+        };
+        for change in action.changes.iter().rev() {
+            change.inverse().apply(store)?;
+        }
+        journal.redo.push(action.clone());
+        self.save(&journal)?;
+        Ok(Some(action))
+    }
+
+    /// Applies the last undone action again, returning it
+    pub fn redo(&self, store: &mut dyn TaskStore) -> Result<Option<Action>> {
+        let mut journal = self.load()?;
+        let Some(action) = journal.redo.pop() else {
+            return Ok(None);
+        };
+        for change in &action.changes {
+            change.apply(store)?;
+        }
+        journal.undo.push(action.clone());
+        self.save(&journal)?;
+        Ok(Some(action))
+    }
+}
@@ -2,10 +2,19 @@ pub mod app;
 pub mod cli;
 pub mod ui;
 
+pub mod date_parser;
 pub mod day_of_week;
+pub mod due;
+pub mod priority;
 pub mod repeat;
+pub mod search;
+pub mod stats;
 pub mod task;
 pub mod task_form;
 
 pub mod configuration;
+pub mod error;
+pub mod history;
+pub mod store;
 pub mod utils;
+pub mod workspace;
@@ -1,18 +1,83 @@
-use gyst_tui::configuration::get_configuration;
+use clap::Parser;
+use gyst_tui::configuration::{
+    PathOverrides, SettingsBuilder, get_configuration, set_path_overrides,
+};
+use gyst_tui::error::LoadError;
+use gyst_tui::utils;
 use gyst_tui::{app::App, cli, ui};
+use std::io::{self, IsTerminal, Write};
+use std::process::ExitCode;
 
-fn main() {
-    // Check args, if none, run ui, else run cli
-    let settings = get_configuration();
-    let app = App::new(settings);
+fn load_app() -> Result<App, LoadError> {
+    let settings = get_configuration()?;
+    App::new(settings)
+}
+
+/// Asks whether a corrupt file should be moved out of the way. Only asks when someone is
+/// there to answer.
+fn confirm_move_aside(error: &LoadError) -> bool {
+    let Some(path) = error.path() else {
+        return false;
+    };
+    if !error.is_corrupt() || !io::stdin().is_terminal() {
+        return false;
+    }
+
+    eprint!(
+        "Move {} aside and start with a fresh one? [y/N] ",
+        path.display()
+    );
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn main() -> ExitCode {
+    let args = cli::Args::parse();
+    set_path_overrides(PathOverrides {
+        config_dir: args.config_dir.clone(),
+        db: args.db.clone(),
+    });
+
+    let app = loop {
+        match load_app() {
+            Ok(app) => break app,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                let Some(path) = e.path().filter(|_| confirm_move_aside(&e)) else {
+                    return ExitCode::FAILURE;
+                };
+                let is_db = SettingsBuilder::get_settings_path().is_ok_and(|p| p != path);
+                match utils::move_aside(path) {
+                    Ok(new_path) => {
+                        eprintln!("Moved the old file to {}", new_path.display());
+                        if is_db {
+                            eprintln!(
+                                "Older copies of your todos can be found with `gyst-tui restore`"
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {:#}", e);
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+        }
+    };
 
-    let res = if std::env::args().len() > 1 {
-        cli::start_cli(app)
-    } else {
-        ui::start_ui(app)
+    // Run the cli if there is a command, else run the ui
+    let res = match args.command {
+        Some(command) => cli::start_cli(app, command),
+        None => ui::start_ui(app),
     };
 
     if let Err(e) = res {
-        eprintln!("{}", e);
+        eprintln!("Error: {:#}", e);
+        return ExitCode::FAILURE;
     }
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+/// How important a task is. Tasks due on the same day are listed from the most important.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Priority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    /// One level up, going back to no priority after urgent
+    pub fn bump(self) -> Priority {
+        match self {
+            Priority::None => Priority::Low,
+            Priority::Low => Priority::Medium,
+            Priority::Medium => Priority::High,
+            Priority::High => Priority::Urgent,
+            Priority::Urgent => Priority::None,
+        }
+    }
+
+    /// The iCalendar PRIORITY, where 1 is the highest and 0 is undefined
+    pub fn to_ical(self) -> u8 {
+        match self {
+            Priority::None => 0,
+            Priority::Low => 7,
+            Priority::Medium => 5,
+            Priority::High => 3,
+            Priority::Urgent => 1,
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Priority, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "none" | "0" => Ok(Priority::None),
+            "low" | "1" => Ok(Priority::Low),
+            "medium" | "med" | "2" => Ok(Priority::Medium),
+            "high" | "3" => Ok(Priority::High),
+            "urgent" | "4" => Ok(Priority::Urgent),
+            _ => Err(anyhow::anyhow!(
+                "Invalid priority '{}', use none, low, medium, high or urgent",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Priority::None => write!(f, "None"),
+            Priority::Low => write!(f, "Low"),
+            Priority::Medium => write!(f, "Medium"),
+            Priority::High => write!(f, "High"),
+            Priority::Urgent => write!(f, "Urgent"),
+        }
+    }
+}
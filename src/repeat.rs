@@ -1,43 +1,757 @@
-use crate::day_of_week::DayOfWeek;
-use anyhow::Result;
+use crate::{day_of_week::DayOfWeek, due::Due};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Datelike, Days, Local, Month, Months, NaiveDate, Weekday};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
 
-#[derive(Serialize, Deserialize, Clone, Eq, PartialEq)]
-pub enum Repeat {
-    Never,
+/// How many periods to look ahead for a date matching a rule, e.g. a February 29th
+const MAX_PERIODS: u32 = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Frequency {
     Daily,
     Weekly,
     Monthly,
     Yearly,
-    DaysOfWeek(Vec<DayOfWeek>),
+}
+
+impl Frequency {
+    fn unit(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "day",
+            Frequency::Weekly => "week",
+            Frequency::Monthly => "month",
+            Frequency::Yearly => "year",
+        }
+    }
+}
+
+/// When a repeating task stops repeating
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub enum End {
+    /// No occurrence is due after this date
+    Until(NaiveDate),
+    /// The number of occurrences left, including the current one
+    Count(u32),
+}
+
+/// What the next occurrence of a repeating task is counted from
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Anchor {
+    /// The due date of the occurrence that was completed, so the schedule stays fixed
+    #[default]
+    Due,
+    /// The day the occurrence was completed, e.g. for chores that are due a week after the last
+    /// time they were done
+    Completion,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct Rule {
+    pub frequency: Frequency,
+    /// Repeats every `interval` days, weeks, months or years
+    pub interval: u32,
+    /// The days of the week the task is due on. In monthly and yearly rules, `ordinal` picks
+    /// one of them.
+    pub days: Vec<DayOfWeek>,
+    /// The day of the month, counting from the end when negative, e.g. -1 is the last day
+    pub month_day: Option<i32>,
+    /// Which of the matching `days` of the month, counting from the end when negative
+    pub ordinal: Option<i32>,
+    /// The month yearly rules on a day of the month are due in, from 1 to 12. Without one, it's
+    /// the month the task is due in.
+    pub month: Option<u32>,
+    pub end: Option<End>,
+    pub anchor: Anchor,
+    /// Skips the occurrences that are already past due when completing a late one, so that the
+    /// next occurrence is never overdue
+    pub catch_up: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub enum Repeat {
+    Never,
+    Every(Rule),
+}
+
+impl Rule {
+    pub fn new(frequency: Frequency) -> Rule {
+        Rule {
+            frequency,
+            interval: 1,
+            days: vec![],
+            month_day: None,
+            ordinal: None,
+            month: None,
+            end: None,
+            anchor: Anchor::Due,
+            catch_up: false,
+        }
+    }
+
+    fn has_day(&self, weekday: Weekday) -> bool {
+        self.days.is_empty() || self.days.iter().any(|d| d.to_chrono() == weekday)
+    }
+
+    /// When the next occurrence after `from` is due, keeping its time of day in its time zone if
+    /// it has one
+    pub fn next_due(&self, from: Due) -> Option<Due> {
+        from.on(self.next_day(from.zoned_date())?)
+    }
+
+    fn next_day(&self, from: NaiveDate) -> Option<NaiveDate> {
+        let interval = self.interval.max(1);
+        match self.frequency {
+            Frequency::Daily => from.checked_add_days(Days::new(interval as u64)),
+            Frequency::Weekly if self.days.is_empty() => {
+                from.checked_add_days(Days::new(7 * interval as u64))
+            }
+            Frequency::Weekly => {
+                // Only weeks that are a multiple of `interval` after the current one count
+                let monday =
+                    |d: NaiveDate| d - Days::new(d.weekday().num_days_from_monday() as u64);
+                let this_week = monday(from);
+                (1..=7 * (interval as u64 + 1))
+                    .map(|i| from + Days::new(i))
+                    .find(|d| {
+                        let weeks = (monday(*d) - this_week).num_weeks();
+                        weeks % interval as i64 == 0 && self.has_day(d.weekday())
+                    })
+            }
+            Frequency::Monthly | Frequency::Yearly => {
+                let months = match self.frequency {
+                    Frequency::Monthly => interval,
+                    _ => 12 * interval,
+                };
+                if self.month_day.is_none() && self.ordinal.is_none() {
+                    return from.checked_add_months(Months::new(months));
+                }
+
+                let first = match self.month {
+                    Some(month) => from.with_day(1)?.with_month(month)?,
+                    None => from.with_day(1)?,
+                };
+                (0..MAX_PERIODS)
+                    .map_while(|i| first.checked_add_months(Months::new(i * months)))
+                    .filter_map(|month| self.day_in_month(month))
+                    .find(|d| *d > from)
+            }
+        }
+    }
+
+    /// The day matching `month_day` or `ordinal` in the month starting on `first`, if any
+    fn day_in_month(&self, first: NaiveDate) -> Option<NaiveDate> {
+        let last = first
+            .checked_add_months(Months::new(1))?
+            .signed_duration_since(first)
+            .num_days() as i32;
+
+        if let Some(month_day) = self.month_day {
+            let day = if month_day > 0 {
+                month_day
+            } else {
+                last + 1 + month_day
+            };
+            return (1..=last)
+                .contains(&day)
+                .then(|| first.with_day(day as u32))
+                .flatten();
+        }
+
+        let ordinal = self.ordinal?;
+        let matching: Vec<NaiveDate> = first
+            .iter_days()
+            .take(last as usize)
+            .filter(|d| self.has_day(d.weekday()))
+            .collect();
+        let idx = if ordinal > 0 {
+            ordinal - 1
+        } else {
+            matching.len() as i32 + ordinal
+        };
+        usize::try_from(idx)
+            .ok()
+            .and_then(|i| matching.get(i).copied())
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.interval == 0 {
+            return Err(anyhow!("The interval must be at least 1"));
+        }
+        if let Some(month_day) = self.month_day
+            && (month_day == 0 || month_day.abs() > 31)
+        {
+            return Err(anyhow!("Invalid day of the month: {}", month_day));
+        }
+        if let Some(ordinal) = self.ordinal
+            && (ordinal == 0 || ordinal.abs() > 5)
+        {
+            return Err(anyhow!("Invalid position in the month: {}", ordinal));
+        }
+        let monthly = matches!(self.frequency, Frequency::Monthly | Frequency::Yearly);
+        if !monthly && (self.month_day.is_some() || self.ordinal.is_some()) {
+            return Err(anyhow!(
+                "Only monthly and yearly tasks can repeat on a day of the month"
+            ));
+        }
+        if let Some(month) = self.month {
+            if self.frequency != Frequency::Yearly {
+                return Err(anyhow!("Only yearly tasks can repeat in a month"));
+            }
+            if !(1..=12).contains(&month) {
+                return Err(anyhow!("Invalid month: {}", month));
+            }
+            if self.month_day.is_none() && self.ordinal.is_none() {
+                return Err(anyhow!(
+                    "Say which day of the month, e.g. 'on the 15th of march'"
+                ));
+            }
+        }
+        if self.frequency == Frequency::Daily && !self.days.is_empty() {
+            return Err(anyhow!("Daily tasks can't repeat on days of the week"));
+        }
+        if monthly && !self.days.is_empty() && self.ordinal.is_none() {
+            return Err(anyhow!(
+                "Say which of the days of the month, e.g. 'the last fri'"
+            ));
+        }
+        if let Some(End::Count(0)) = self.end {
+            return Err(anyhow!("A task must repeat at least once"));
+        }
+        Ok(())
+    }
+
+    /// Whether this is one of the rules of the original Daily/Weekly/Monthly/Yearly options
+    fn is_simple(&self) -> bool {
+        self.interval == 1
+            && self.days.is_empty()
+            && self.month_day.is_none()
+            && self.ordinal.is_none()
+            && self.month.is_none()
+            && self.end.is_none()
+            && self.has_default_schedule()
+    }
+
+    /// Whether the rule counts from the due date and doesn't skip missed occurrences
+    fn has_default_schedule(&self) -> bool {
+        self.anchor == Anchor::Due && !self.catch_up
+    }
 }
 
 impl Repeat {
+    pub fn every(frequency: Frequency) -> Repeat {
+        Repeat::Every(Rule::new(frequency))
+    }
+
+    /// When the occurrence after the one due at `due` and completed at `completed` is due,
+    /// along with the rule that the next occurrence repeats with. Returns `None` if the task
+    /// doesn't repeat anymore.
+    pub fn next(&self, due: Due, completed: DateTime<Local>) -> Option<(Due, Repeat)> {
+        let Repeat::Every(rule) = self else {
+            return None;
+        };
+        let from = match rule.anchor {
+            Anchor::Completion => due.on(completed.date_naive())?,
+            Anchor::Due => due,
+        };
+
+        let mut rule = rule.clone();
+        let mut next = from;
+        loop {
+            if let Some(End::Count(count)) = &mut rule.end {
+                if *count <= 1 {
+                    return None;
+                }
+                *count -= 1;
+            }
+            next = rule.next_due(next)?;
+            if let Some(End::Until(until)) = rule.end
+                && next.date_naive() > until
+            {
+                return None;
+            }
+            // Missed occurrences count towards the end of the rule as well
+            if !rule.catch_up || next.date_naive() >= completed.date_naive() {
+                break;
+            }
+        }
+        Some((next, Repeat::Every(rule)))
+    }
+
+    /// Parses either one of the simple options (never, daily, weekly, monthly, yearly or a list
+    /// of days like `mon,thu`), a rule like `every 2 weeks on mon,thu until 2027-01-01` or an
+    /// iCalendar RRULE. Any of them but never and RRULEs can end with `from completion` and
+    /// `skipping missed`.
     pub fn parse_from_str(s: &str) -> Result<Repeat> {
-        match s.to_lowercase().as_str() {
-            "never" | "" => Ok(Repeat::Never),
-            "daily" => Ok(Repeat::Daily),
-            "weekly" => Ok(Repeat::Weekly),
-            "monthly" => Ok(Repeat::Monthly),
-            "yearly" => Ok(Repeat::Yearly),
-            _ => {
-                let days: Vec<Result<DayOfWeek>> = s
-                    .split(',')
-                    .map(|s| s.trim())
-                    .map(DayOfWeek::from_str)
-                    .collect();
-                if days.iter().any(|d| d.is_err()) {
-                    return Err(anyhow::anyhow!("Invalid day of the week"));
+        let upper = s.trim().to_uppercase();
+        if upper.starts_with("RRULE:") || upper.starts_with("FREQ=") {
+            return Repeat::from_rrule(s);
+        }
+
+        let s = s.to_lowercase().replace(", ", ",");
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let mut parser = Parser { words, pos: 0 };
+
+        let mut rule = match parser.next() {
+            None => return Ok(Repeat::Never),
+            Some("never") if parser.done() => return Ok(Repeat::Never),
+            Some("daily") => Rule::new(Frequency::Daily),
+            Some("weekly") => Rule::new(Frequency::Weekly),
+            Some("monthly") => Rule::new(Frequency::Monthly),
+            Some("yearly") => Rule::new(Frequency::Yearly),
+            Some("every") => parser.every()?,
+            Some(word) => {
+                let mut rule = Rule::new(Frequency::Weekly);
+                rule.days = parse_days(word)?;
+                rule
+            }
+        };
+        parser.rest(&mut rule)?;
+        rule.validate()?;
+        Ok(Repeat::Every(rule))
+    }
+}
+
+impl Repeat {
+    /// The RFC 5545 RRULE value of this repeat, e.g. `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH`.
+    /// Tasks that don't repeat have none. RRULEs have no anchor or catch-up, so those are left
+    /// out.
+    ///
+    /// Yearly RRULEs on a day of the month need BYMONTH, or they mean every month or the whole
+    /// year. Rules without a month take it from `due`, and have no RRULE without it. UNTIL is a
+    /// time in UTC when `due` has a time, as it must have the same type as the start date.
+    pub fn to_rrule(&self, due: Option<&Due>) -> Option<String> {
+        let Repeat::Every(rule) = self else {
+            return None;
+        };
+        let on_a_day = rule.month_day.is_some() || rule.ordinal.is_some();
+        let month = match rule.frequency {
+            Frequency::Yearly if on_a_day => {
+                Some(rule.month.or_else(|| due.map(|d| d.zoned_date().month()))?)
+            }
+            _ => None,
+        };
+
+        let frequency = match rule.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        let mut parts = vec![format!("FREQ={}", frequency)];
+        if rule.interval != 1 {
+            parts.push(format!("INTERVAL={}", rule.interval));
+        }
+        if let Some(month) = month {
+            parts.push(format!("BYMONTH={}", month));
+        }
+
+        let days = rule.days.iter().map(day_to_rrule).join(",");
+        match (rule.ordinal, rule.days.len()) {
+            (None, 0) => {}
+            (None, _) => parts.push(format!("BYDAY={}", days)),
+            (Some(ordinal), 0) => parts.push(format!("BYMONTHDAY={}", ordinal)),
+            (Some(ordinal), 1) => parts.push(format!("BYDAY={}{}", ordinal, days)),
+            (Some(ordinal), _) => {
+                parts.push(format!("BYDAY={}", days));
+                parts.push(format!("BYSETPOS={}", ordinal));
+            }
+        }
+        if let Some(month_day) = rule.month_day {
+            parts.push(format!("BYMONTHDAY={}", month_day));
+        }
+
+        match &rule.end {
+            Some(End::Until(date)) => {
+                let until = match due {
+                    Some(due) if due.has_time() => Due::Date(*date)
+                        .deadline()
+                        .naive_utc()
+                        .format("%Y%m%dT%H%M%SZ")
+                        .to_string(),
+                    _ => date.format("%Y%m%d").to_string(),
+                };
+                parts.push(format!("UNTIL={}", until));
+            }
+            Some(End::Count(count)) => parts.push(format!("COUNT={}", count)),
+            None => {}
+        }
+        Some(parts.join(";"))
+    }
+
+    /// Parses an RFC 5545 RRULE, with or without the `RRULE:` prefix. Only the parts that gyst
+    /// can represent are accepted: FREQ (daily to yearly), INTERVAL, BYMONTH, BYDAY,
+    /// BYMONTHDAY, BYSETPOS, COUNT and UNTIL. Yearly rules on a day of the month need a single
+    /// BYMONTH.
+    pub fn from_rrule(s: &str) -> Result<Repeat> {
+        let s = s.trim();
+        let s = match s.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("RRULE:") => &s[6..],
+            _ => s,
+        };
+
+        let mut frequency = None;
+        let mut rule = Rule::new(Frequency::Daily);
+        let mut set_position = None;
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid RRULE part '{}'", part))?;
+            let value = value.to_uppercase();
+            let number = |value: &str| -> Result<i32> {
+                value
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid number '{}' in {}", value, key))
+            };
+
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(anyhow!("Unsupported frequency '{}'", value)),
+                    })
+                }
+                "INTERVAL" => rule.interval = number(&value)?.try_into()?,
+                "BYDAY" => {
+                    let mut ordinals = vec![];
+                    for day in value.split(',') {
+                        let weekday = day.trim_start_matches(|c: char| {
+                            c == '+' || c == '-' || c.is_ascii_digit()
+                        });
+                        let ordinal = &day[..day.len() - weekday.len()];
+                        rule.days.push(day_from_rrule(weekday)?);
+                        ordinals.push(match ordinal {
+                            "" | "+" => None,
+                            ordinal => Some(number(ordinal.trim_start_matches('+'))?),
+                        });
+                    }
+                    if !ordinals.iter().all_equal() {
+                        return Err(anyhow!("Days with different positions are not supported"));
+                    }
+                    rule.ordinal = ordinals.into_iter().next().flatten();
+                }
+                "BYMONTH" => rule.month = Some(number(&value)?.try_into()?),
+                "BYMONTHDAY" => rule.month_day = Some(number(&value)?),
+                "BYSETPOS" => set_position = Some(number(&value)?),
+                "COUNT" => rule.end = Some(End::Count(number(&value)?.try_into()?)),
+                "UNTIL" => {
+                    let date = value.get(..8).unwrap_or(&value);
+                    let date = NaiveDate::parse_from_str(date, "%Y%m%d")
+                        .map_err(|_| anyhow!("Invalid UNTIL date '{}'", value))?;
+                    rule.end = Some(End::Until(date));
                 }
+                "WKST" => {}
+                _ => return Err(anyhow!("Unsupported RRULE part '{}'", key)),
+            }
+        }
+
+        rule.frequency = frequency.ok_or_else(|| anyhow!("Missing FREQ in RRULE"))?;
+        if rule.frequency == Frequency::Daily && !rule.days.is_empty() && rule.interval == 1 {
+            // e.g. every weekday
+            rule.frequency = Frequency::Weekly;
+        }
+        if let Some(position) = set_position {
+            if rule.ordinal.is_some() || rule.days.is_empty() {
+                return Err(anyhow!("BYSETPOS is only supported along with BYDAY"));
+            }
+            rule.ordinal = Some(position);
+        }
+        let on_a_day = rule.month_day.is_some() || rule.ordinal.is_some();
+        if rule.frequency == Frequency::Yearly && on_a_day && rule.month.is_none() {
+            return Err(anyhow!(
+                "Yearly RRULEs on a day of the month need BYMONTH, e.g. BYMONTH=11;BYDAY=4TH"
+            ));
+        }
+        rule.validate()?;
+        Ok(Repeat::Every(rule))
+    }
+}
+
+fn day_to_rrule(day: &DayOfWeek) -> &'static str {
+    match day {
+        DayOfWeek::Monday => "MO",
+        DayOfWeek::Tuesday => "TU",
+        DayOfWeek::Wednesday => "WE",
+        DayOfWeek::Thursday => "TH",
+        DayOfWeek::Friday => "FR",
+        DayOfWeek::Saturday => "SA",
+        DayOfWeek::Sunday => "SU",
+    }
+}
 
-                let days: Vec<DayOfWeek> =
-                    days.iter().map(|d| d.as_ref().unwrap()).cloned().collect();
+fn day_from_rrule(day: &str) -> Result<DayOfWeek> {
+    match day {
+        "MO" => Ok(DayOfWeek::Monday),
+        "TU" => Ok(DayOfWeek::Tuesday),
+        "WE" => Ok(DayOfWeek::Wednesday),
+        "TH" => Ok(DayOfWeek::Thursday),
+        "FR" => Ok(DayOfWeek::Friday),
+        "SA" => Ok(DayOfWeek::Saturday),
+        "SU" => Ok(DayOfWeek::Sunday),
+        _ => Err(anyhow!("Invalid day '{}' in BYDAY", day)),
+    }
+}
+
+/// Reads the words of a rule one at a time
+struct Parser<'a> {
+    words: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        let word = self.words.get(self.pos).copied();
+        self.pos += 1;
+        word
+    }
 
-                Ok(Repeat::DaysOfWeek(days))
+    fn peek(&self) -> Option<&'a str> {
+        self.words.get(self.pos).copied()
+    }
+
+    fn done(&self) -> bool {
+        self.pos >= self.words.len()
+    }
+
+    fn expect(&mut self, what: &str) -> Result<&'a str> {
+        self.next().ok_or_else(|| anyhow!("Expected {}", what))
+    }
+
+    /// What follows "every": an interval and unit, or days of the week
+    fn every(&mut self) -> Result<Rule> {
+        let mut word = self.expect("how often, e.g. 'every 2 weeks'")?;
+        let interval = match word.parse::<u32>() {
+            Ok(interval) => {
+                word = self.expect("a unit, e.g. 'weeks'")?;
+                interval
+            }
+            Err(_) => 1,
+        };
+
+        let frequency = match word.trim_end_matches('s') {
+            "day" => Frequency::Daily,
+            "week" => Frequency::Weekly,
+            "month" => Frequency::Monthly,
+            "year" => Frequency::Yearly,
+            _ if interval == 1 => {
+                let mut rule = Rule::new(Frequency::Weekly);
+                rule.days = parse_days(word)?;
+                return Ok(rule);
             }
+            _ => return Err(anyhow!("Unknown unit '{}'", word)),
+        };
+        let mut rule = Rule::new(frequency);
+        rule.interval = interval;
+        Ok(rule)
+    }
+
+    /// The optional "on ..." and end condition
+    fn rest(&mut self, rule: &mut Rule) -> Result<()> {
+        while let Some(word) = self.next() {
+            match word {
+                "on" => self.on(rule)?,
+                "of" | "in" => {
+                    let month = self.expect("a month after 'of'")?;
+                    let month =
+                        Month::from_str(month).map_err(|_| anyhow!("Invalid month '{}'", month))?;
+                    rule.month = Some(month.number_from_month());
+                }
+                "until" => {
+                    let date = self.expect("a date after 'until'")?;
+                    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                        .map_err(|_| anyhow!("Invalid date '{}', use YYYY-MM-DD", date))?;
+                    rule.end = Some(End::Until(date));
+                }
+                "for" => {
+                    let count = self.expect("a number of times")?;
+                    rule.end = Some(End::Count(parse_count(count)?));
+                    if self.peek() == Some("times") || self.peek() == Some("time") {
+                        self.next();
+                    }
+                }
+                "from" | "after" => {
+                    rule.anchor = match self.expect("'due' or 'completion'")? {
+                        "due" => Anchor::Due,
+                        "completion" | "completed" | "done" => Anchor::Completion,
+                        word => {
+                            return Err(anyhow!(
+                                "Expected 'due' or 'completion' after 'from', found '{}'",
+                                word
+                            ));
+                        }
+                    };
+                    if self.peek() == Some("date") {
+                        self.next();
+                    }
+                }
+                "skipping" | "skip" => {
+                    if self.expect("'missed' after 'skipping'")? != "missed" {
+                        return Err(anyhow!("Expected 'missed' after 'skipping'"));
+                    }
+                    rule.catch_up = true;
+                }
+                _ => return Err(anyhow!("Unexpected '{}'", word)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Days of the week, a day of the month or the nth day of the week of the month
+    fn on(&mut self, rule: &mut Rule) -> Result<()> {
+        if self.peek() == Some("the") {
+            self.next();
+        }
+        let word = self.expect("what to repeat on")?;
+
+        // "on day 15"
+        if word == "day" {
+            let day = self.expect("a day of the month")?;
+            rule.month_day = Some(parse_ordinal(day)?);
+            return Ok(());
+        }
+        if let Ok(days) = parse_days(word) {
+            rule.days = days;
+            return Ok(());
+        }
+
+        // "the 15th", "the last day", "the 2nd last fri", "the first weekday"
+        let mut position = parse_ordinal(word)?;
+        if word != "last" && self.peek() == Some("last") {
+            self.next();
+            position = -position;
+        }
+        match self.peek() {
+            Some("day") => {
+                self.next();
+                rule.month_day = Some(position);
+            }
+            Some(days) if parse_days(days).is_ok() => {
+                self.next();
+                rule.days = parse_days(days)?;
+                rule.ordinal = Some(position);
+            }
+            _ if word.starts_with(|c: char| c.is_ascii_digit()) => rule.month_day = Some(position),
+            _ => {
+                return Err(anyhow!(
+                    "Expected 'day' or days of the week after '{}'",
+                    word
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A comma separated list of days, or "weekday" or "weekend"
+fn parse_days(s: &str) -> Result<Vec<DayOfWeek>> {
+    match s {
+        "weekday" | "weekdays" => Ok(DayOfWeek::WEEKDAYS.to_vec()),
+        "weekend" | "weekends" => Ok(DayOfWeek::WEEKEND.to_vec()),
+        _ => s
+            .split(',')
+            .map(|d| DayOfWeek::from_str(d.trim()))
+            .collect::<Result<Vec<DayOfWeek>>>()
+            .map_err(|_| anyhow!("Invalid day of the week in '{}'", s)),
+    }
+}
+
+/// "first" to "fifth", "last", or a number like "15th"
+fn parse_ordinal(s: &str) -> Result<i32> {
+    let n = match s {
+        "first" => 1,
+        "second" => 2,
+        "third" => 3,
+        "fourth" => 4,
+        "fifth" => 5,
+        "last" => -1,
+        _ => s
+            .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+            .parse()
+            .map_err(|_| anyhow!("Invalid position '{}'", s))?,
+    };
+    Ok(n)
+}
+
+fn parse_count(s: &str) -> Result<u32> {
+    s.trim_start_matches('x')
+        .parse()
+        .map_err(|_| anyhow!("Invalid number of times '{}'", s))
+}
+
+fn ordinal_to_str(n: i32) -> String {
+    let suffix = match (n.abs() % 10, n.abs() % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    match n {
+        -1 => "last".to_string(),
+        n if n < 0 => format!("{}{} last", -n, suffix),
+        n => format!("{}{}", n, suffix),
+    }
+}
+
+/// "first" to "fifth" or "last", e.g. in "the first mon"
+fn position_to_str(n: i32) -> String {
+    let words = ["first", "second", "third", "fourth", "fifth"];
+    match n {
+        1..=5 => words[n as usize - 1].to_string(),
+        _ => ordinal_to_str(n),
+    }
+}
+
+fn days_to_str(days: &[DayOfWeek]) -> String {
+    if days == DayOfWeek::WEEKDAYS {
+        "weekday".to_string()
+    } else if days == DayOfWeek::WEEKEND {
+        "weekend".to_string()
+    } else {
+        days.iter().map(|d| d.to_string().to_lowercase()).join(",")
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.interval {
+            1 => write!(f, "every {}", self.frequency.unit())?,
+            n => write!(f, "every {} {}s", n, self.frequency.unit())?,
+        }
+        match (self.month_day, self.ordinal) {
+            (Some(-1), _) => write!(f, " on the last day")?,
+            (Some(day), _) => write!(f, " on the {}", ordinal_to_str(day))?,
+            (None, Some(ordinal)) if self.days.is_empty() => {
+                write!(f, " on the {} day", position_to_str(ordinal))?
+            }
+            (None, Some(ordinal)) => write!(
+                f,
+                " on the {} {}",
+                position_to_str(ordinal),
+                days_to_str(&self.days)
+            )?,
+            (None, None) if !self.days.is_empty() => write!(f, " on {}", days_to_str(&self.days))?,
+            (None, None) => {}
+        }
+        if let Some(month) = self.month.and_then(|m| Month::try_from(m as u8).ok()) {
+            write!(f, " of {}", month.name().to_lowercase())?;
+        }
+        match &self.end {
+            Some(End::Until(date)) => write!(f, " until {}", date.format("%Y-%m-%d"))?,
+            Some(End::Count(count)) => write!(f, " for {} times", count)?,
+            None => {}
+        }
+        if self.anchor == Anchor::Completion {
+            write!(f, " from completion")?;
+        }
+        if self.catch_up {
+            write!(f, " skipping missed")?;
         }
+        Ok(())
     }
 }
 
@@ -45,14 +759,22 @@ impl Display for Repeat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Repeat::Never => write!(f, "Never"),
-            Repeat::Daily => write!(f, "Daily"),
-            Repeat::Weekly => write!(f, "Weekly"),
-            Repeat::Monthly => write!(f, "Monthly"),
-            Repeat::Yearly => write!(f, "Yearly"),
-            Repeat::DaysOfWeek(days) => {
-                let days = days.iter().map(|d| d.to_string()).join(",");
+            Repeat::Every(rule) if rule.is_simple() => match rule.frequency {
+                Frequency::Daily => write!(f, "Daily"),
+                Frequency::Weekly => write!(f, "Weekly"),
+                Frequency::Monthly => write!(f, "Monthly"),
+                Frequency::Yearly => write!(f, "Yearly"),
+            },
+            Repeat::Every(rule)
+                if rule.frequency == Frequency::Weekly
+                    && rule.interval == 1
+                    && rule.end.is_none()
+                    && rule.has_default_schedule() =>
+            {
+                let days = rule.days.iter().map(|d| d.to_string()).join(",");
                 write!(f, "{}", days)
             }
+            Repeat::Every(rule) => write!(f, "{}", rule),
         }
     }
 }
@@ -0,0 +1,58 @@
+use crate::task::Task;
+use anyhow::{Result, anyhow};
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// Finds tasks by the text in their name, description, url or group. Shared by the search
+/// prompt of the TUI and `ls --search`.
+pub struct Search {
+    query: String,
+    regex: Regex,
+}
+
+impl Search {
+    /// Searches for `query` as plain text, or as a regular expression with `is_regex`
+    pub fn new(query: &str, is_regex: bool, ignore_case: bool) -> Result<Search> {
+        let pattern = match is_regex {
+            true => query.to_string(),
+            false => regex::escape(query),
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|e| anyhow!("Invalid search '{}': {}", query, e))?;
+        Ok(Search {
+            query: query.to_string(),
+            regex,
+        })
+    }
+
+    /// Searches for `query` as plain text, ignoring case unless it has any capitals like vim's
+    /// smartcase
+    pub fn smart_case(query: &str) -> Search {
+        let ignore_case = !query.chars().any(char::is_uppercase);
+        Self::new(query, false, ignore_case).expect("Plain text is always a valid search")
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Whether the name, description, url or group of `task` has a match
+    pub fn matches(&self, task: &Task) -> bool {
+        std::iter::once(task.name.as_str())
+            .chain(task.description.as_deref())
+            .chain(task.url.as_deref())
+            .chain(task.group.as_deref())
+            .any(|text| self.regex.is_match(text))
+    }
+
+    /// Where the matches are in `text`, as byte ranges to highlight
+    pub fn find(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .map(|m| m.range())
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate};
+use itertools::Itertools;
+
+use crate::{app::Id, task::Task};
+
+/// Completion history of a task and of every other occurrence in its series
+pub struct Stats {
+    /// When each occurrence was completed, oldest first
+    pub completions: Vec<DateTime<Local>>,
+    /// Number of occurrences in a row, up to now, completed by their due date
+    pub current_streak: usize,
+    pub longest_streak: usize,
+}
+
+impl Stats {
+    pub fn for_task(tasks: &HashMap<Id, Task>, id: Id) -> Option<Stats> {
+        let task = tasks.get(&id)?;
+        let occurrences: Vec<&Task> = match task.series {
+            Some(series) => tasks
+                .values()
+                .filter(|t| t.series == Some(series))
+                .sorted_by_key(|t| t.date)
+                .collect(),
+            None => vec![task],
+        };
+        Some(Self::from_occurrences(
+            &occurrences,
+            Local::now().date_naive(),
+        ))
+    }
+
+    /// Each occurrence is due on the next date of the series' `Repeat`, so a streak is broken by
+    /// an occurrence completed late or still open after its due date
+    fn from_occurrences(occurrences: &[&Task], today: NaiveDate) -> Stats {
+        let mut completions = vec![];
+        let mut streak = 0;
+        let mut longest_streak = 0;
+        for task in occurrences {
+            let Some(date) = task.date else {
+                continue;
+            };
+            let due = date.date_naive();
+            if task.complete {
+                // Completions from before their time was recorded count as on time
+                let completed_at = task.completed_at.unwrap_or(date.deadline());
+                completions.push(completed_at);
+                if completed_at.date_naive() <= due {
+                    streak += 1;
+                    longest_streak = longest_streak.max(streak);
+                } else {
+                    streak = 0;
+                }
+            } else if due < today {
+                streak = 0;
+            }
+        }
+        completions.sort();
+
+        Stats {
+            completions,
+            current_streak: streak,
+            longest_streak,
+        }
+    }
+
+    /// Number of completions on each day of the last `weeks` weeks, ending with the week of
+    /// `today`. Returns one row per weekday, from Monday to Sunday, with one column per week.
+    /// Days after `today` are `None`.
+    pub fn heatmap(&self, today: NaiveDate, weeks: usize) -> Vec<Vec<Option<usize>>> {
+        let this_monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+        let start = this_monday - Days::new(7 * weeks.saturating_sub(1) as u64);
+
+        let mut rows = vec![vec![Some(0); weeks]; 7];
+        if weeks == 0 {
+            return rows;
+        }
+        let days_this_week = today.weekday().num_days_from_monday() as usize + 1;
+        for row in rows.iter_mut().skip(days_this_week) {
+            row[weeks - 1] = None;
+        }
+        for completion in &self.completions {
+            let day = completion.date_naive();
+            if day < start || day > today {
+                continue;
+            }
+            let offset = (day - start).num_days() as usize;
+            if let Some(count) = &mut rows[offset % 7][offset / 7] {
+                *count += 1;
+            }
+        }
+        rows
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result, anyhow};
+
+use super::TaskStore;
+use crate::{app::Id, error::LoadError, task::Task, utils};
+
+/// Identifies a version of the database file, to notice when another process replaced it
+type Fingerprint = Option<(SystemTime, u64)>;
+
+fn fingerprint(path: &Path) -> Fingerprint {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Keeps every task in a single JSON file, which is rewritten on each change
+pub struct JsonStore {
+    path: PathBuf,
+    backup_dir: PathBuf,
+    backups: usize,
+    tasks: HashMap<Id, Task>,
+    current_id: Id,
+    fingerprint: Fingerprint,
+    changed_externally: bool,
+}
+
+impl JsonStore {
+    pub fn open(path: &Path, backup_dir: &Path, backups: usize) -> Result<Self, LoadError> {
+        let fingerprint = fingerprint(path);
+        let tasks = utils::load_tasks(path)?;
+        let current_id = tasks.keys().copied().max().unwrap_or(0);
+        Ok(JsonStore {
+            path: path.to_path_buf(),
+            backup_dir: backup_dir.to_path_buf(),
+            backups,
+            tasks,
+            current_id,
+            fingerprint,
+            changed_externally: false,
+        })
+    }
+
+    /// Takes the advisory lock shared by every gyst process using this file. It is released
+    /// when the returned file is dropped.
+    fn lock(&self) -> Result<File> {
+        let lock_path = utils::append_to_file_name(&self.path, ".lock");
+        let lock = File::create(&lock_path)
+            .with_context(|| format!("Unable to create {}", lock_path.display()))?;
+        lock.lock()
+            .with_context(|| format!("Unable to lock {}", lock_path.display()))?;
+        Ok(lock)
+    }
+
+    /// Reads the file again if another process changed it since we last did. Must be called
+    /// with the lock held.
+    fn reload_if_changed(&mut self) -> Result<bool> {
+        let fingerprint = fingerprint(&self.path);
+        if fingerprint == self.fingerprint {
+            return Ok(false);
+        }
+
+        self.tasks = utils::load_tasks(&self.path)?;
+        self.current_id = self
+            .current_id
+            .max(self.tasks.keys().copied().max().unwrap_or(0));
+        self.fingerprint = fingerprint;
+        Ok(true)
+    }
+
+    /// Applies a change on top of the latest version of the file and saves it, all while
+    /// holding the lock so that no other process can write in between
+    fn modify<T>(&mut self, change: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let _lock = self.lock()?;
+        if self.reload_if_changed()? {
+            self.changed_externally = true;
+        }
+
+        let result = change(self)?;
+        utils::save_tasks(&self.path, &self.tasks, &self.backup_dir, self.backups)?;
+        self.fingerprint = fingerprint(&self.path);
+        Ok(result)
+    }
+}
+
+impl TaskStore for JsonStore {
+    fn tasks(&self) -> &HashMap<Id, Task> {
+        &self.tasks
+    }
+
+    fn insert(&mut self, mut task: Task) -> Result<Id> {
+        self.modify(|store| {
+            let id = match task.id {
+                Some(id) => id,
+                None => {
+                    store.current_id += 1;
+                    store.current_id
+                }
+            };
+            store.current_id = store.current_id.max(id);
+            task.id = Some(id);
+            store.tasks.insert(id, task);
+            Ok(id)
+        })
+    }
+
+    fn update(&mut self, task: Task) -> Result<()> {
+        let id = task
+            .id
+            .ok_or_else(|| anyhow!("Cannot update a task without an id"))?;
+        self.modify(|store| {
+            if !store.tasks.contains_key(&id) {
+                return Err(anyhow!("Task with id {} not found", id));
+            }
+            store.tasks.insert(id, task);
+            Ok(())
+        })
+    }
+
+    fn delete(&mut self, id: Id) -> Result<Option<Task>> {
+        self.modify(|store| Ok(store.tasks.remove(&id)))
+    }
+
+    fn replace_all(&mut self, tasks: Vec<Task>) -> Result<()> {
+        let mut new_tasks = HashMap::new();
+        for task in tasks {
+            let id = task
+                .id
+                .ok_or_else(|| anyhow!("Cannot store a task without an id"))?;
+            new_tasks.insert(id, task);
+        }
+        self.modify(|store| {
+            store.current_id = store
+                .current_id
+                .max(new_tasks.keys().copied().max().unwrap_or(0));
+            store.tasks = new_tasks;
+            Ok(())
+        })
+    }
+
+    fn refresh(&mut self) -> Result<bool> {
+        let changed = std::mem::take(&mut self.changed_externally);
+        if fingerprint(&self.path) == self.fingerprint {
+            return Ok(changed);
+        }
+
+        let _lock = self.lock()?;
+        Ok(self.reload_if_changed()? || changed)
+    }
+}
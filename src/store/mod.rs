@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{app::Id, error::LoadError, task::Task};
+
+mod json;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use json::JsonStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+/// Where tasks are persisted
+pub trait TaskStore {
+    /// All the tasks in the store, by id
+    fn tasks(&self) -> &HashMap<Id, Task>;
+
+    fn get(&self, id: Id) -> Option<&Task> {
+        self.tasks().get(&id)
+    }
+
+    /// Adds a task to the store, giving it a new id unless it already has one. Returns the id
+    /// of the stored task.
+    fn insert(&mut self, task: Task) -> Result<Id>;
+
+    /// Replaces the stored task with the same id
+    fn update(&mut self, task: Task) -> Result<()>;
+
+    /// Removes a task from the store, returning it if it existed
+    fn delete(&mut self, id: Id) -> Result<Option<Task>>;
+
+    /// Replaces every task in the store with `tasks`, keeping their ids
+    fn replace_all(&mut self, tasks: Vec<Task>) -> Result<()>;
+
+    /// Reloads the tasks if another process changed them since they were last read. Returns
+    /// whether anything changed, including changes picked up while saving.
+    fn refresh(&mut self) -> Result<bool>;
+
+    fn query(&self, filter: &dyn Fn(&Task) -> bool) -> Vec<&Task> {
+        self.tasks().values().filter(|t| filter(t)).collect()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Storage {
+    #[default]
+    Json,
+    Sqlite,
+}
+
+impl Storage {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Storage::Json => "tasks.json",
+            Storage::Sqlite => "tasks.db",
+        }
+    }
+}
+
+/// Opens the task store of the given kind at `path`
+pub fn open(
+    storage: Storage,
+    path: &Path,
+    backup_dir: &Path,
+    backups: usize,
+) -> Result<Box<dyn TaskStore>, LoadError> {
+    match storage {
+        Storage::Json => Ok(Box::new(JsonStore::open(path, backup_dir, backups)?)),
+        #[cfg(feature = "sqlite")]
+        Storage::Sqlite => Ok(Box::new(SqliteStore::open(path)?)),
+        #[cfg(not(feature = "sqlite"))]
+        Storage::Sqlite => Err(LoadError::storage(
+            path,
+            "gyst-tui was built without SQLite support, rebuild it with `--features sqlite`",
+        )),
+    }
+}
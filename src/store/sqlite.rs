@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde_json::{Map, Value, json};
+
+use super::TaskStore;
+use crate::{app::Id, error::LoadError, task::Task, utils};
+
+/// Keeps each task in its own row of an SQLite database, so changes only touch that row
+pub struct SqliteStore {
+    conn: Connection,
+    path: PathBuf,
+    tasks: HashMap<Id, Task>,
+    current_id: Id,
+    data_version: i64,
+    changed_externally: bool,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<Self, LoadError> {
+        let conn = Connection::open(path).map_err(|e| LoadError::storage(path, e))?;
+        Self::setup(&conn).map_err(|e| LoadError::storage(path, e))?;
+        let tasks = Self::load(&conn, path)?;
+        let current_id = tasks.keys().copied().max().unwrap_or(0);
+        let data_version = Self::data_version(&conn).map_err(|e| LoadError::storage(path, e))?;
+        Ok(SqliteStore {
+            conn,
+            path: path.to_path_buf(),
+            tasks,
+            current_id,
+            data_version,
+            changed_externally: false,
+        })
+    }
+
+    /// Changes whenever another connection commits to the database
+    fn data_version(conn: &Connection) -> rusqlite::Result<i64> {
+        conn.query_row("PRAGMA data_version", [], |row| row.get(0))
+    }
+
+    /// Reads every task again if another connection changed the database since we last did
+    fn reload_if_changed(&mut self) -> Result<bool> {
+        let data_version = Self::data_version(&self.conn)?;
+        if data_version == self.data_version {
+            return Ok(false);
+        }
+
+        self.tasks = Self::load(&self.conn, &self.path)?;
+        self.current_id = self
+            .current_id
+            .max(self.tasks.keys().copied().max().unwrap_or(0));
+        self.data_version = data_version;
+        Ok(true)
+    }
+
+    /// Applies a change inside a write transaction, after catching up with any change made by
+    /// another process, so that neither overwrites the other
+    fn modify<T>(&mut self, change: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        let result = self.reload_if_changed().and_then(|changed| {
+            if changed {
+                self.changed_externally = true;
+            }
+            change(self)
+        });
+        match result {
+            Ok(result) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(result)
+            }
+            Err(e) => {
+                self.conn.execute_batch("ROLLBACK")?;
+                Err(e)
+            }
+        }
+    }
+
+    fn setup(conn: &Connection) -> rusqlite::Result<()> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS tasks (id INTEGER PRIMARY KEY, data TEXT NOT NULL);",
+        )?;
+        // A new database starts at the current version
+        conn.execute(
+            "INSERT OR IGNORE INTO meta (key, value) VALUES ('schema_version', ?1)",
+            params![utils::SCHEMA_VERSION.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Reads every task, upgrading the stored rows first if they use an older schema
+    fn load(conn: &Connection, path: &Path) -> Result<HashMap<Id, Task>, LoadError> {
+        let to_err = |e: rusqlite::Error| LoadError::storage(path, e);
+        let version: u32 = conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'schema_version'",
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(to_err)?
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| LoadError::schema(path, "Missing schema version"))?;
+        utils::ensure_supported(version, path)?;
+
+        let mut stmt = conn.prepare("SELECT id, data FROM tasks").map_err(to_err)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(to_err)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(to_err)?;
+
+        let mut tasks = Map::new();
+        for (id, data) in rows {
+            let task: Value = serde_json::from_str(&data).map_err(|e| LoadError::parse(path, e))?;
+            tasks.insert(id.to_string(), task);
+        }
+        let db = json!({ "schema_version": version, "tasks": tasks });
+        let db = utils::migrate(db).map_err(|e| LoadError::schema(path, e))?;
+        let db: utils::Database<HashMap<Id, Task>> =
+            serde_json::from_value(db).map_err(|e| LoadError::schema(path, e))?;
+
+        if version != utils::SCHEMA_VERSION {
+            conn.unchecked_transaction()
+                .map_err(anyhow::Error::from)
+                .and_then(|tx| {
+                    Self::rewrite(&tx, &db.tasks)?;
+                    Ok(tx.commit()?)
+                })
+                .map_err(|e| LoadError::storage(path, e))?;
+        }
+        Ok(db.tasks)
+    }
+
+    /// Replaces every row with the given tasks at the current schema version. Must be called
+    /// inside a transaction.
+    fn rewrite(conn: &Connection, tasks: &HashMap<Id, Task>) -> Result<()> {
+        conn.execute("DELETE FROM tasks", [])?;
+        for (id, task) in tasks {
+            conn.execute(
+                "INSERT INTO tasks (id, data) VALUES (?1, ?2)",
+                params![*id as i64, serde_json::to_string(task)?],
+            )?;
+        }
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![utils::SCHEMA_VERSION.to_string()],
+        )?;
+        Ok(())
+    }
+
+    fn write(&self, task: &Task) -> Result<()> {
+        let id = task
+            .id
+            .ok_or_else(|| anyhow!("Cannot save a task without an id"))?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO tasks (id, data) VALUES (?1, ?2)",
+            params![id as i64, serde_json::to_string(task)?],
+        )?;
+        Ok(())
+    }
+}
+
+impl TaskStore for SqliteStore {
+    fn tasks(&self) -> &HashMap<Id, Task> {
+        &self.tasks
+    }
+
+    fn insert(&mut self, mut task: Task) -> Result<Id> {
+        self.modify(|store| {
+            let id = match task.id {
+                Some(id) => id,
+                None => {
+                    store.current_id += 1;
+                    store.current_id
+                }
+            };
+            store.current_id = store.current_id.max(id);
+            task.id = Some(id);
+            store.write(&task)?;
+            store.tasks.insert(id, task);
+            Ok(id)
+        })
+    }
+
+    fn update(&mut self, task: Task) -> Result<()> {
+        let id = task
+            .id
+            .ok_or_else(|| anyhow!("Cannot update a task without an id"))?;
+        self.modify(|store| {
+            if !store.tasks.contains_key(&id) {
+                return Err(anyhow!("Task with id {} not found", id));
+            }
+            store.write(&task)?;
+            store.tasks.insert(id, task);
+            Ok(())
+        })
+    }
+
+    fn delete(&mut self, id: Id) -> Result<Option<Task>> {
+        self.modify(|store| {
+            store
+                .conn
+                .execute("DELETE FROM tasks WHERE id = ?1", params![id as i64])?;
+            Ok(store.tasks.remove(&id))
+        })
+    }
+
+    fn replace_all(&mut self, tasks: Vec<Task>) -> Result<()> {
+        let mut new_tasks = HashMap::new();
+        for task in tasks {
+            let id = task
+                .id
+                .ok_or_else(|| anyhow!("Cannot store a task without an id"))?;
+            new_tasks.insert(id, task);
+        }
+        self.modify(|store| {
+            Self::rewrite(&store.conn, &new_tasks)?;
+            store.current_id = store
+                .current_id
+                .max(new_tasks.keys().copied().max().unwrap_or(0));
+            store.tasks = new_tasks;
+            Ok(())
+        })
+    }
+
+    fn refresh(&mut self) -> Result<bool> {
+        let changed = std::mem::take(&mut self.changed_externally);
+        Ok(self.reload_if_changed()? || changed)
+    }
+}
@@ -1,7 +1,13 @@
-use crate::{day_of_week::DayOfWeek, repeat::Repeat};
+use crate::due::Due;
+use crate::priority::Priority;
+use crate::repeat::Repeat;
 use anyhow::Result;
-use chrono::{DateTime, Datelike, Days, Duration, Local, Months, TimeZone};
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
 
 pub fn serialize_dt<S>(date: &DateTime<Local>, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -11,26 +17,61 @@ where
     serializer.serialize_str(&s)
 }
 
-pub fn deserialize_dt<'de, D>(deserializer: D) -> Result<DateTime<Local>, D::Error>
+pub fn serialize_optional_dt<S>(
+    date: &Option<DateTime<Local>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match date {
+        Some(date) => serialize_dt(date, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize_optional_dt<'de, D>(deserializer: D) -> Result<Option<DateTime<Local>>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    let s = String::deserialize(deserializer)?;
-    let dt = Local.datetime_from_str(&s, "%+").unwrap();
-    Ok(dt)
+    let s = Option::<String>::deserialize(deserializer)?;
+    s.map(|s| {
+        let dt = DateTime::parse_from_str(&s, "%+").map_err(serde::de::Error::custom)?;
+        Ok(dt.with_timezone(&Local))
+    })
+    .transpose()
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Task {
     pub id: Option<usize>,
+    /// Shared by every occurrence of a repeating task, while each occurrence has its own id
+    pub series: Option<Uuid>,
+    /// The task this is a step of
+    pub parent: Option<usize>,
     pub name: String,
-    #[serde(serialize_with = "serialize_dt", deserialize_with = "deserialize_dt")]
-    pub date: DateTime<Local>,
+    /// Tasks without a date are kept for someday, and can't repeat
+    pub date: Option<Due>,
+    /// Tasks are hidden until their start date, e.g. renewing a passport that's due in three
+    /// months but can't be done before next month
+    pub start: Option<Due>,
     pub repeats: Repeat,
+    pub priority: Priority,
     pub group: Option<String>,
+    /// Contexts the task belongs to besides its group, e.g. home or errand
+    pub tags: Vec<String>,
+    /// The ids of the tasks that have to be complete before this one can start
+    pub blocked_by: Vec<usize>,
     pub description: Option<String>,
     pub url: Option<String>,
     pub complete: bool,
+    /// When the task was marked as complete
+    #[serde(
+        default,
+        serialize_with = "serialize_optional_dt",
+        deserialize_with = "deserialize_optional_dt"
+    )]
+    pub completed_at: Option<DateTime<Local>>,
 }
 
 impl Task {
@@ -38,22 +79,75 @@ impl Task {
         self.id = id;
     }
 
+    pub fn set_parent(&mut self, parent: Option<usize>) {
+        self.parent = parent;
+    }
+
     pub fn set_name(&mut self, name: String) {
         self.name = name;
     }
 
-    pub fn set_date(&mut self, date: DateTime<Local>) {
+    pub fn set_date(&mut self, date: Option<Due>) {
         self.date = date;
     }
 
+    pub fn set_start(&mut self, start: Option<Due>) {
+        self.start = start;
+    }
+
+    /// Whether the task can't be started yet
+    pub fn is_deferred(&self, now: DateTime<Local>) -> bool {
+        self.start.is_some_and(|start| !start.is_reached(now))
+    }
+
+    /// Orders tasks by when they're due, with the tasks without a date last
+    pub fn cmp_date(&self, other: &Task) -> Ordering {
+        match (&self.date, &other.date) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    /// Orders tasks by the day they're due, then from the most important, then by when they're
+    /// due on that day
+    pub fn cmp_day_and_priority(&self, other: &Task) -> Ordering {
+        let day = |t: &Task| (t.date.is_none(), t.date.map(|d| d.date_naive()));
+        day(self)
+            .cmp(&day(other))
+            .then_with(|| other.priority.cmp(&self.priority))
+            .then_with(|| self.cmp_date(other))
+    }
+
     pub fn set_repeats(&mut self, repeats: Repeat) {
+        if repeats != Repeat::Never && self.series.is_none() {
+            self.series = Some(Uuid::new_v4());
+        }
         self.repeats = repeats;
     }
 
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority;
+    }
+
     pub fn set_group(&mut self, group: String) {
         self.group = Some(group);
     }
 
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags;
+    }
+
+    /// Whether the task has `tag`, in any case
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    pub fn set_blocked_by(&mut self, blocked_by: Vec<usize>) {
+        self.blocked_by = blocked_by;
+    }
+
     pub fn set_description(&mut self, description: String) {
         self.description = Some(description);
     }
@@ -64,38 +158,27 @@ impl Task {
 
     pub fn set_complete(&mut self) -> Option<Task> {
         self.complete = true;
-        let date = match &self.repeats {
-            Repeat::DaysOfWeek(days) => {
-                let mut new_date = None;
-                for i in 1..=7 {
-                    let day = self.date + Duration::days(i);
-                    let weekday = DayOfWeek::from_chrono(day.weekday());
-                    if days.contains(&weekday) {
-                        new_date = Some(day);
-                        break;
-                    }
-                }
-                new_date
-            }
-            Repeat::Never => None,
-            Repeat::Daily => Some(self.date + Days::new(1)),
-            Repeat::Weekly => Some(self.date + Days::new(7)),
-            Repeat::Monthly => Some(self.date + Months::new(1)),
-            Repeat::Yearly => Some(self.date + Months::new(12)),
-        };
-
-        if let Some(date) = date {
-            let mut new_task = self.clone();
-            new_task.set_date(date);
-            new_task.set_incomplete();
-            Some(new_task)
-        } else {
-            None
-        }
+        let now = Local::now();
+        self.completed_at = Some(now);
+        let date = self.date?;
+        let (next, repeats) = self.repeats.next(date, now)?;
+        // The start moves by as many days as the due date, to stay as far ahead of it
+        let start = self.start.and_then(|start| {
+            let days = next.zoned_date() - date.zoned_date();
+            start.on(start.zoned_date().checked_add_signed(days)?)
+        });
+        let mut new_task = self.clone();
+        new_task.set_id(None);
+        new_task.set_date(Some(next));
+        new_task.set_start(start);
+        new_task.set_repeats(repeats);
+        new_task.set_incomplete();
+        Some(new_task)
     }
 
     pub fn set_incomplete(&mut self) -> Option<Task> {
         self.complete = false;
+        self.completed_at = None;
         None
     }
 
@@ -112,13 +195,61 @@ impl Default for Task {
     fn default() -> Self {
         Self {
             id: None,
+            series: None,
+            parent: None,
             name: "".to_string(),
-            date: Local::now(),
+            date: None,
+            start: None,
             repeats: Repeat::Never,
+            priority: Priority::None,
             group: None,
+            tags: vec![],
+            blocked_by: vec![],
             description: None,
             url: None,
             complete: false,
+            completed_at: None,
         }
     }
 }
+
+/// Orders `tasks` so that subtasks come right after their parent, keeping the order of `tasks`
+/// otherwise, along with how deep each task is. Tasks whose parent isn't in `tasks` are at the
+/// top.
+pub fn as_tree<T: Borrow<Task>>(tasks: Vec<T>) -> Vec<(T, usize)> {
+    let ids: HashSet<usize> = tasks.iter().filter_map(|t| t.borrow().id).collect();
+    let mut roots = vec![];
+    let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (idx, task) in tasks.iter().enumerate() {
+        let task = task.borrow();
+        match task
+            .parent
+            .filter(|p| ids.contains(p) && task.id != Some(*p))
+        {
+            Some(parent) => children.entry(parent).or_default().push(idx),
+            None => roots.push(idx),
+        }
+    }
+
+    let mut order = vec![];
+    let mut seen = HashSet::new();
+    // Tasks in a loop of parents have no root, so they go at the end
+    for root in roots.into_iter().chain(0..tasks.len()) {
+        let mut stack = vec![(root, 0)];
+        while let Some((idx, depth)) = stack.pop() {
+            if !seen.insert(idx) {
+                continue;
+            }
+            order.push((idx, depth));
+            if let Some(children) = tasks[idx].borrow().id.and_then(|id| children.get(&id)) {
+                stack.extend(children.iter().rev().map(|&child| (child, depth + 1)));
+            }
+        }
+    }
+
+    let mut tasks: Vec<Option<T>> = tasks.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .filter_map(|(idx, depth)| Some((tasks[idx].take()?, depth)))
+        .collect()
+}
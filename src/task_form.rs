@@ -1,7 +1,12 @@
+use std::collections::HashSet;
+
 use anyhow::Context;
 use anyhow::Result;
+use chrono::{DateTime, Local};
+use uuid::Uuid;
 
 use crate::configuration::Settings;
+use crate::priority::Priority;
 use crate::repeat::Repeat;
 use crate::task::Task;
 use crate::utils;
@@ -9,24 +14,52 @@ use crate::utils;
 #[derive(Default)]
 pub struct TaskForm {
     pub id: Option<usize>,
+    pub series: Option<Uuid>,
+    pub parent: Option<usize>,
     pub name: String,
     pub date: String,
+    pub start: String,
     pub repeats: String,
+    pub priority: String,
     pub group: String,
+    pub tags: String,
+    pub blocked_by: String,
     pub description: String,
     pub url: String,
+    /// Not edited in the form, but kept so editing a completed task doesn't reopen it
+    pub complete: bool,
+    pub completed_at: Option<DateTime<Local>>,
 }
 
 impl TaskForm {
     pub fn from_task(task: &Task, settings: &Settings) -> Self {
         Self {
             id: task.id,
+            series: task.series,
+            parent: task.parent,
             name: task.name.to_string(),
-            date: utils::date_to_input_str(&task.date, settings),
+            date: task
+                .date
+                .map(|date| utils::date_to_input_str(&date, settings))
+                .unwrap_or_default(),
+            start: task
+                .start
+                .map(|start| utils::date_to_input_str(&start, settings))
+                .unwrap_or_default(),
             repeats: task.repeats.to_string(),
+            priority: task.priority.to_string(),
             group: task.group.clone().unwrap_or_default(),
+            tags: task.tags.join(", "),
+            blocked_by: task
+                .blocked_by
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
             description: task.description.clone().unwrap_or_default(),
             url: task.url.clone().unwrap_or_default(),
+            complete: task.complete,
+            completed_at: task.completed_at,
         }
     }
 
@@ -34,26 +67,98 @@ impl TaskForm {
         let mut task = Task::default();
 
         let repeat = Repeat::parse_from_str(&self.repeats).context("Invalid repeat format")?;
-        let date = utils::parse_date(&self.date, settings).unwrap_or(utils::get_today());
+        let priority: Priority = self.priority.parse()?;
+        let date = match self.date.trim() {
+            "" => None,
+            date => Some(utils::parse_date(date, settings)?),
+        };
+        if date.is_none() && repeat != Repeat::Never {
+            return Err(anyhow::anyhow!("Repeating tasks need a date"));
+        }
+        let start = match self.start.trim() {
+            "" => None,
+            start => Some(utils::parse_date(start, settings).context("Invalid start date")?),
+        };
+        if let (Some(start), Some(date)) = (start, date)
+            && start.date_naive() > date.date_naive()
+        {
+            return Err(anyhow::anyhow!(
+                "The start date can't be after the due date"
+            ));
+        }
+
+        let (name, mut tags) = split_tags(&self.name);
+        for tag in self
+            .tags
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(|t| t.trim_start_matches(['#', '+']))
+            .filter(|t| !t.is_empty())
+        {
+            tags.push(tag.to_string());
+        }
+        let mut seen = HashSet::new();
+        tags.retain(|tag| seen.insert(tag.to_lowercase()));
+
+        let blocked_by = self
+            .blocked_by
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(|id| id.trim_start_matches('#'))
+            .filter(|id| !id.is_empty())
+            .map(|id| {
+                id.parse::<usize>()
+                    .map_err(|_| anyhow::anyhow!("Invalid task id '{}'", id))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        if self.name.is_empty() {
+        if name.is_empty() {
             return Err(anyhow::anyhow!("Task name cannot be empty"));
         }
 
         task.set_id(self.id);
-        task.set_name(self.name.clone());
+        task.series = self.series;
+        task.set_parent(self.parent);
+        task.set_name(name);
         task.set_date(date);
+        task.set_start(start);
         task.set_repeats(repeat);
+        task.set_priority(priority);
         if !self.group.is_empty() {
             task.set_group(self.group.clone());
         }
+        task.set_tags(tags);
+        task.set_blocked_by(blocked_by);
         if !self.description.is_empty() {
             task.set_description(self.description.clone());
         }
         if !self.url.is_empty() {
             task.set_url(self.url.clone());
         }
+        task.complete = self.complete;
+        task.completed_at = self.completed_at;
 
         Ok(task)
     }
 }
+
+/// Splits the `+tag` and `#tag` words off a task name, e.g. "Buy milk +errand" is "Buy milk"
+/// with the tag "errand"
+fn split_tags(name: &str) -> (String, Vec<String>) {
+    let mut words = vec![];
+    let mut tags = vec![];
+    for word in name.split_whitespace() {
+        let tag = word
+            .strip_prefix('#')
+            .or_else(|| word.strip_prefix('+'))
+            // Not "#1" or "C++"
+            .filter(|t| t.chars().any(char::is_alphabetic))
+            .filter(|t| t.chars().all(|c| c.is_alphanumeric() || "-_/".contains(c)));
+        match tag {
+            Some(tag) => tags.push(tag.to_string()),
+            None => words.push(word),
+        }
+    }
+    if tags.is_empty() {
+        return (name.to_string(), tags);
+    }
+    (words.join(" "), tags)
+}
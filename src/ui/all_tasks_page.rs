@@ -1,12 +1,17 @@
 use crate::app::App;
+use crate::configuration::Settings;
+use crate::due::Due;
 use crate::repeat::Repeat;
-use crate::task::Task;
+use crate::search::Search;
+use crate::task::{self, Task};
 use crate::ui::Page;
 use crate::utils;
 use anyhow::Result;
-use chrono::{DateTime, Local, TimeZone};
+use chrono::{DateTime, Local};
+use crossterm::event::KeyCode;
 use itertools::Itertools;
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::rc::Rc;
 use tui::layout::{Direction, Rect};
 use tui::text::{Line, Span};
@@ -19,23 +24,44 @@ use tui::{
 
 pub struct AllTasksPage {
     pub show_hidden: bool,
+    pub show_deferred: bool,
     pub current_id: Option<usize>,
+    /// The task waiting for an answer on whether to complete its subtasks too
+    pub completing: Option<usize>,
+    /// Whether the search prompt is open, so typing goes to the search
+    pub searching: bool,
     pub app: Rc<RefCell<App>>,
 
     current_group: Option<String>,
+    current_tag: Option<String>,
+    /// Only the tasks matching it are shown
+    search: Option<Search>,
+    collapsed: HashSet<usize>,
+    /// Why the view could not be saved, to show as a notice
+    save_error: Option<String>,
 }
 
 impl AllTasksPage {
     pub fn new(app: Rc<RefCell<App>>) -> AllTasksPage {
-        let show_hidden = app.borrow().settings.show_complete;
-        let current_group = app.borrow().settings.current_group.clone();
+        let show_hidden = app.borrow().settings.show_complete();
+        let show_deferred = app.borrow().settings.show_deferred();
+        let current_group = app.borrow().settings.current_group();
+        let current_tag = app.borrow().settings.current_tag();
 
         let mut atp = AllTasksPage {
             show_hidden,
+            show_deferred,
             current_id: None,
+            completing: None,
+            searching: false,
             current_group,
+            current_tag,
+            search: None,
+            collapsed: HashSet::new(),
+            save_error: None,
             app,
         };
+        atp.ensure_tag_exists();
 
         let any_in_group = atp
             .visible_tasks()
@@ -48,12 +74,18 @@ impl AllTasksPage {
         atp
     }
 
-    /// Returns the tasks that should be displayed on the page
+    /// Returns the tasks that should be displayed on the page, in the order they're displayed
     pub fn visible_tasks(&self) -> Vec<Task> {
+        self.tree().into_iter().map(|(t, _)| t).collect()
+    }
+
+    /// The visible tasks with their subtasks below them, along with how deep they are. The
+    /// subtasks of collapsed tasks are left out.
+    fn tree(&self) -> Vec<(Task, usize)> {
         // Borrow app only once, clone the tasks, then drop the borrow
         let tasks: Vec<Task> = {
             let app = self.app.borrow();
-            app.tasks.values().cloned().collect()
+            app.tasks().values().cloned().collect()
         };
 
         let tasks: Vec<Task> = if !self.show_hidden {
@@ -61,6 +93,12 @@ impl AllTasksPage {
         } else {
             tasks
         };
+        let tasks: Vec<Task> = if !self.show_deferred {
+            let now = Local::now();
+            tasks.into_iter().filter(|t| !t.is_deferred(now)).collect()
+        } else {
+            tasks
+        };
 
         // Filter out tasks not in the current group
         let tasks: Vec<Task> = if let Some(group) = &self.current_group {
@@ -72,13 +110,38 @@ impl AllTasksPage {
             tasks
         };
 
-        tasks
+        let tasks: Vec<Task> = if let Some(tag) = &self.current_tag {
+            tasks.into_iter().filter(|t| t.has_tag(tag)).collect()
+        } else {
+            tasks
+        };
+
+        let tasks: Vec<Task> = if let Some(search) = &self.search {
+            tasks.into_iter().filter(|t| search.matches(t)).collect()
+        } else {
+            tasks
+        };
+
+        let tasks: Vec<Task> = tasks
             .into_iter()
-            .sorted_by(|a, b| a.date.cmp(&b.date))
-            .collect()
+            .sorted_by(|a, b| a.cmp_day_and_priority(b))
+            .collect();
+
+        let mut tree = vec![];
+        let mut collapsed_depth = None;
+        for (task, depth) in task::as_tree(tasks) {
+            if collapsed_depth.is_some_and(|collapsed| depth > collapsed) {
+                continue;
+            }
+            let collapsed = self.collapsed.contains(&task.id.unwrap());
+            collapsed_depth = collapsed.then_some(depth);
+            tree.push((task, depth));
+        }
+        tree
     }
 
     pub fn ensure_group_exists(&mut self) {
+        self.ensure_tag_exists();
         // Check that there are still visible tasks in group
         let any = self
             .visible_tasks()
@@ -89,6 +152,16 @@ impl AllTasksPage {
         }
     }
 
+    /// Stops filtering by a tag that no task has anymore
+    pub fn ensure_tag_exists(&mut self) {
+        if let Some(tag) = &self.current_tag {
+            let any = self.app.borrow().tasks().values().any(|t| t.has_tag(tag));
+            if !any {
+                self.set_tag(None);
+            }
+        }
+    }
+
     pub fn ensure_task_exists(&mut self) {
         // Check that the current task still exists
         if let Some(id) = self.current_id {
@@ -99,16 +172,120 @@ impl AllTasksPage {
         }
     }
 
-    /// Toggles the complete status of the currently selected task
-    pub fn toggle_selected(&mut self) {
+    /// Toggles the complete status of the currently selected task. Completing a task with open
+    /// subtasks asks first whether to complete them too, returning the question.
+    pub fn toggle_selected(&mut self) -> Option<String> {
         if let Some(task_id) = self.current_id {
-            self.app.borrow_mut().toggle_complete_task(task_id);
+            let open_subtasks = {
+                let app = self.app.borrow();
+                let task = app.get_task(task_id)?;
+                let open = app
+                    .descendants(task_id)
+                    .into_iter()
+                    .filter(|&id| app.get_task(id).is_some_and(|t| !t.complete))
+                    .count();
+                (!task.complete && open > 0).then(|| (task.name.clone(), open))
+            };
+            if let Some((name, open)) = open_subtasks {
+                self.completing = Some(task_id);
+                return Some(format!(
+                    "Also complete the {} open subtasks of '{}'? (y/n)",
+                    open, name
+                ));
+            }
+
+            let blocked = self.blocked_ids();
+            let result = self.app.borrow_mut().toggle_complete_task(task_id);
 
             if !self.show_hidden {
                 self.move_closest();
             }
+            self.ensure_group_exists();
+            return match result {
+                Ok(_) => self.unblocked_notice(&blocked),
+                Err(e) => Some(format!("{:#}", e)),
+            };
+        }
+        self.ensure_group_exists();
+        None
+    }
+
+    /// Completes the task waiting for an answer, with its subtasks on y and without them on n.
+    /// Any other key cancels.
+    pub fn answer_completing(&mut self, code: KeyCode) -> Option<String> {
+        let task_id = self.completing.take()?;
+        let blocked = self.blocked_ids();
+        let result = match code {
+            KeyCode::Char('y') => self.app.borrow_mut().complete_with_subtasks(task_id),
+            KeyCode::Char('n') => self.app.borrow_mut().set_complete(task_id, true),
+            _ => return None,
+        };
+        if !self.show_hidden {
+            self.move_closest();
         }
         self.ensure_group_exists();
+        match result {
+            Ok(_) => self.unblocked_notice(&blocked),
+            Err(e) => Some(format!("{:#}", e)),
+        }
+    }
+
+    /// The ids of the tasks waiting for another task to be complete
+    fn blocked_ids(&self) -> HashSet<usize> {
+        let app = self.app.borrow();
+        app.tasks()
+            .values()
+            .filter(|t| app.is_blocked(t))
+            .filter_map(|t| t.id)
+            .collect()
+    }
+
+    /// Names the tasks out of `blocked` that aren't waiting for anything anymore
+    fn unblocked_notice(&self, blocked: &HashSet<usize>) -> Option<String> {
+        let app = self.app.borrow();
+        let names = blocked
+            .iter()
+            .filter_map(|&id| app.get_task(id))
+            .filter(|t| !t.complete && !app.is_blocked(t))
+            .map(|t| format!("'{}'", t.name))
+            .sorted()
+            .join(", ");
+        (!names.is_empty()).then(|| format!("{} can start now", names))
+    }
+
+    /// Collapses or expands the subtasks of the selected task, or of its parent if it has none
+    pub fn toggle_collapsed(&mut self) {
+        let Some(task_id) = self.current_id else {
+            return;
+        };
+        let target = {
+            let app = self.app.borrow();
+            if app.subtasks(task_id).is_empty() {
+                app.get_task(task_id).and_then(|t| t.parent)
+            } else {
+                Some(task_id)
+            }
+        };
+        if let Some(target) = target {
+            if !self.collapsed.remove(&target) {
+                self.collapsed.insert(target);
+            }
+            if self.visible_tasks().iter().any(|t| t.id == Some(target)) {
+                self.current_id = Some(target);
+            }
+        }
+    }
+
+    /// Raises the priority of the currently selected task, going back to none after urgent
+    pub fn bump_selected_priority(&mut self) -> Result<()> {
+        if let Some(task_id) = self.current_id {
+            let task = self.app.borrow().get_task(task_id).cloned();
+            if let Some(mut task) = task {
+                task.set_priority(task.priority.bump());
+                self.app.borrow_mut().update_task(task)?;
+            }
+        }
+        Ok(())
     }
 
     pub fn next(&mut self) {
@@ -158,7 +335,109 @@ impl AllTasksPage {
                 self.current_id = None;
             }
         }
-        self.app.borrow_mut().settings.set_current_group(group);
+        self.save_view(|settings| settings.set_current_group(group));
+    }
+
+    /// Saves a change to the view, which is remembered in the settings
+    fn save_view(&mut self, save: impl FnOnce(&mut Settings) -> Result<()>) {
+        if let Err(e) = save(&mut self.app.borrow_mut().settings) {
+            self.save_error = Some(format!("Unable to save settings: {:#}", e));
+        }
+    }
+
+    pub fn take_save_error(&mut self) -> Option<String> {
+        self.save_error.take()
+    }
+
+    pub fn get_current_tag(&self) -> Option<String> {
+        self.current_tag.clone()
+    }
+
+    pub fn set_tag(&mut self, tag: Option<String>) {
+        self.current_tag = tag.clone();
+        self.save_view(|settings| settings.set_current_tag(tag));
+        self.ensure_group_exists();
+        self.ensure_task_exists();
+    }
+
+    /// Opens the search prompt with an empty search
+    pub fn start_search(&mut self) {
+        self.searching = true;
+        self.search = Some(Search::smart_case(""));
+    }
+
+    /// Adds a character to the search, selecting the first match if the selected task isn't one
+    pub fn add_search_char(&mut self, c: char) {
+        let mut query = self.get_search_query().unwrap_or_default();
+        query.push(c);
+        self.set_search(&query);
+    }
+
+    /// Removes the last character of the search, or cancels it if it's already empty
+    pub fn remove_search_char(&mut self) {
+        let mut query = self.get_search_query().unwrap_or_default();
+        if query.pop().is_none() {
+            self.clear_search();
+            return;
+        }
+        self.set_search(&query);
+    }
+
+    fn set_search(&mut self, query: &str) {
+        self.search = Some(Search::smart_case(query));
+        self.ensure_task_exists();
+        if self.current_id.is_none() {
+            self.next();
+        }
+    }
+
+    /// Closes the search prompt, keeping the tasks filtered unless the search is empty
+    pub fn finish_search(&mut self) {
+        self.searching = false;
+        if self.get_search_query().is_some_and(|q| q.is_empty()) {
+            self.search = None;
+        }
+        self.ensure_group_exists();
+    }
+
+    /// Shows all the tasks again
+    pub fn clear_search(&mut self) {
+        self.searching = false;
+        self.search = None;
+        self.ensure_group_exists();
+        if self.current_id.is_none() {
+            self.move_closest();
+        }
+    }
+
+    pub fn get_search_query(&self) -> Option<String> {
+        self.search.as_ref().map(|s| s.query().to_string())
+    }
+
+    /// Selects the next task matching the search, going back to the first one after the last
+    pub fn next_match(&mut self) {
+        let tasks = self.visible_tasks();
+        let idx = self
+            .current_id
+            .and_then(|id| tasks.iter().position(|t| t.id == Some(id)));
+        let next = match idx {
+            Some(idx) => tasks.get(idx + 1).or(tasks.first()),
+            None => tasks.first(),
+        };
+        self.current_id = next.and_then(|t| t.id);
+    }
+
+    /// Selects the previous task matching the search, going to the last one before the first
+    pub fn prev_match(&mut self) {
+        let tasks = self.visible_tasks();
+        let idx = self
+            .current_id
+            .and_then(|id| tasks.iter().position(|t| t.id == Some(id)));
+        let prev = match idx {
+            Some(idx) if idx > 0 => tasks.get(idx - 1),
+            _ => tasks.last(),
+        };
+        self.current_id = prev.and_then(|t| t.id);
     }
 
     pub fn next_group(&mut self) {
@@ -177,41 +456,43 @@ impl AllTasksPage {
                 }
             }
         }
-        self.app
-            .borrow_mut()
-            .settings
-            .set_current_group(self.current_group.clone());
+        let group = self.current_group.clone();
+        self.save_view(|settings| settings.set_current_group(group));
     }
 
     pub fn prev_group(&mut self) {
         let groups = self.get_groups();
         self.current_id = None;
-        match &self.current_group {
-            Some(group) => {
-                let idx = groups.iter().position(|g| g == group).unwrap();
-                if idx > 1 {
-                    self.current_group = Some(groups[idx - 1].clone());
-                } else {
-                    self.current_group = None;
-                }
+        if let Some(group) = &self.current_group {
+            let idx = groups.iter().position(|g| g == group).unwrap();
+            if idx > 1 {
+                self.current_group = Some(groups[idx - 1].clone());
+            } else {
+                self.current_group = None;
             }
-            None => {}
         }
-        self.app
-            .borrow_mut()
-            .settings
-            .set_current_group(self.current_group.clone());
+        let group = self.current_group.clone();
+        self.save_view(|settings| settings.set_current_group(group));
     }
 
-    pub fn groups(&self) -> Vec<Vec<Task>> {
-        self.visible_tasks()
+    /// The visible tasks by day, with the tasks without a date in a last group. Subtasks are in
+    /// the group of their parent.
+    pub fn groups(&self) -> Vec<Vec<(Task, usize)>> {
+        let mut day = None;
+        self.tree()
             .into_iter()
-            .group_by(|t| t.date.date_naive())
+            .map(|(task, depth)| {
+                if depth == 0 {
+                    day = task.date.map(|d| d.date_naive());
+                }
+                (day, task, depth)
+            })
+            .group_by(|(day, _, _)| *day)
             .into_iter()
             .map(|(_, group)| {
                 group
-                    .sorted_by(|a, b| a.date.cmp(&b.date))
-                    .collect::<Vec<Task>>()
+                    .map(|(_, task, depth)| (task, depth))
+                    .collect::<Vec<_>>()
             })
             .collect()
     }
@@ -219,7 +500,12 @@ impl AllTasksPage {
     pub fn move_closest(&mut self) {
         let current_date: Option<DateTime<Local>> = {
             match self.current_id {
-                Some(id) => self.app.borrow().get_task(id).map(|t| t.date),
+                Some(id) => self
+                    .app
+                    .borrow()
+                    .get_task(id)
+                    .and_then(|t| t.date)
+                    .map(|d| d.deadline()),
                 None => None,
             }
         };
@@ -227,11 +513,13 @@ impl AllTasksPage {
         // Move to next task if any, else previous, else none
         let tasks = self.visible_tasks();
         let current_date = current_date.unwrap_or_else(Local::now);
-        let closest = tasks.iter().min_by_key(|t| {
-            t.date
+        let closest = tasks.iter().min_by_key(|t| match t.date {
+            Some(date) => date
+                .deadline()
                 .signed_duration_since(current_date)
                 .num_seconds()
-                .abs()
+                .abs(),
+            None => i64::MAX,
         });
         match closest {
             Some(task) => self.current_id = Some(task.id.unwrap()),
@@ -241,25 +529,39 @@ impl AllTasksPage {
 
     pub fn toggle_hidden(&mut self) {
         self.show_hidden = !self.show_hidden;
-        self.app
-            .borrow_mut()
-            .settings
-            .set_show_complete(self.show_hidden);
+        let show_hidden = self.show_hidden;
+        self.save_view(|settings| settings.set_show_complete(show_hidden));
         self.ensure_group_exists();
         if !self.show_hidden {
             self.move_closest();
         }
     }
 
+    pub fn toggle_deferred(&mut self) {
+        self.show_deferred = !self.show_deferred;
+        let show_deferred = self.show_deferred;
+        self.save_view(|settings| settings.set_show_deferred(show_deferred));
+        self.ensure_group_exists();
+        if !self.show_deferred {
+            self.move_closest();
+        }
+    }
+
     pub fn get_groups(&self) -> Vec<String> {
         let mut groups = vec!["All Tasks".to_string()];
-        let tasks: Vec<Task> = self.app.borrow().tasks.values().cloned().collect();
+        let tasks: Vec<Task> = self.app.borrow().tasks().values().cloned().collect();
 
         let tasks: Vec<Task> = if !self.show_hidden {
             tasks.into_iter().filter(|t| !t.complete).collect()
         } else {
             tasks
         };
+        let tasks: Vec<Task> = if !self.show_deferred {
+            let now = Local::now();
+            tasks.into_iter().filter(|t| !t.is_deferred(now)).collect()
+        } else {
+            tasks
+        };
         let mut other_groups = tasks
             .iter()
             .filter_map(|t| t.group.clone())
@@ -269,6 +571,34 @@ impl AllTasksPage {
         groups
     }
 
+    /// How many subtasks of a task are complete, e.g. "3/5 ", and whether they're collapsed
+    pub fn get_progress(&self, task_id: usize) -> String {
+        let app = self.app.borrow();
+        let subtasks = app.subtasks(task_id);
+        if subtasks.is_empty() {
+            return String::new();
+        }
+        let done = subtasks.iter().filter(|t| t.complete).count();
+        let collapsed = if self.collapsed.contains(&task_id) {
+            "… "
+        } else {
+            ""
+        };
+        format!("{}/{} {}", done, subtasks.len(), collapsed)
+    }
+
+    /// "Todos", followed by the tag and search the tasks are filtered by
+    pub fn get_title(&self) -> String {
+        let mut title = "Todos".to_string();
+        if let Some(tag) = &self.current_tag {
+            title.push_str(&format!(" #{}", tag));
+        }
+        if let Some(query) = self.get_search_query().filter(|q| !q.is_empty()) {
+            title.push_str(&format!(" /{}", query));
+        }
+        title
+    }
+
     pub fn get_complete_icon(&self, complete: bool) -> String {
         self.app.borrow().settings.icons.get_complete_icon(complete)
     }
@@ -280,7 +610,34 @@ impl AllTasksPage {
         }
     }
 
-    pub fn date_to_str(&self, date: &DateTime<Local>) -> String {
+    pub fn get_blocked_icon(&self, task: &Task) -> String {
+        let app = self.app.borrow();
+        match app.is_blocked(task) {
+            true => format!(" {}", app.settings.icons.blocked),
+            false => String::from(""),
+        }
+    }
+
+    /// Splits `text` into spans, highlighting the parts matching the search
+    pub fn highlight_matches(&self, text: &str, style: Style) -> Vec<Span<'static>> {
+        let Some(search) = &self.search else {
+            return vec![Span::styled(text.to_string(), style)];
+        };
+        let colors = &self.app.borrow().settings.colors;
+        let highlight = style.fg(colors.foreground_dark).bg(colors.accent_color);
+
+        let mut spans = vec![];
+        let mut start = 0;
+        for range in search.find(text) {
+            spans.push(Span::styled(text[start..range.start].to_string(), style));
+            spans.push(Span::styled(text[range.clone()].to_string(), highlight));
+            start = range.end;
+        }
+        spans.push(Span::styled(text[start..].to_string(), style));
+        spans
+    }
+
+    pub fn date_to_str(&self, date: &Due) -> String {
         utils::date_to_display_str(date, &self.app.borrow().settings)
     }
 
@@ -338,12 +695,15 @@ impl Page for AllTasksPage {
             .constraints([Constraint::Percentage(100)].as_ref())
             .split(chunks[1]);
 
+        let now = Local::now();
         let mut rows = vec![];
         for group in self.groups() {
             // Group title
-            let group_date = &group[0].date.date_naive().and_hms_opt(23, 59, 59).unwrap();
-            let group_date = Local.from_local_datetime(group_date).unwrap();
-            let date_str = self.date_to_str(&group_date).to_uppercase();
+            let date_str = match group[0].0.date {
+                Some(date) => self.date_to_str(&Due::Date(date.date_naive())),
+                None => "No date".to_string(),
+            }
+            .to_uppercase();
             let group_title = " ".to_string() + date_str.as_str();
             let cell = Cell::from(Span::styled(
                 group_title,
@@ -355,7 +715,7 @@ impl Page for AllTasksPage {
             let pre_count = rows.len();
 
             // All tasks in group
-            for (idx, item) in group.iter().enumerate() {
+            for (idx, (item, depth)) in group.iter().enumerate() {
                 // Skip if hidden
                 if !self.show_hidden && item.complete {
                     continue;
@@ -364,16 +724,38 @@ impl Page for AllTasksPage {
                 // Create string
                 let complete_icon = self.get_complete_icon(item.complete);
                 let recurring_icon = self.get_repeats_icon(&item.repeats);
-                let title = format!("{} {} {} ", complete_icon, item.name, recurring_icon);
+                let indent = "  ".repeat(*depth);
+                let progress = self.get_progress(item.id.unwrap());
+                let blocked_icon = self.get_blocked_icon(item);
+                let prefix = format!("{}{} ", indent, complete_icon);
+                let suffix = format!(" {}{}{} ", progress, recurring_icon, blocked_icon);
                 let title_style = match (item.complete, self.current_id) {
                     (_, Some(task_id)) if task_id == item.id.unwrap() => Style::default()
                         .fg(colors.secondary_color)
                         .add_modifier(Modifier::BOLD),
                     (true, _) => Style::default().fg(Color::DarkGray),
+                    _ if item.is_deferred(now) => Style::default().fg(Color::DarkGray),
+                    _ if !blocked_icon.is_empty() => Style::default().fg(Color::DarkGray),
                     _ => Style::default().fg(Color::White),
                 };
                 let title_style = title_style.add_modifier(Modifier::BOLD);
-                let title_cell = Line::from(Span::styled(title, title_style));
+                let mut spans = vec![Span::styled(prefix, title_style)];
+                spans.extend(self.highlight_matches(&item.name, title_style));
+                spans.push(Span::styled(suffix, title_style));
+                if let Some(color) = colors.priority_color(item.priority) {
+                    let icon = self.app.borrow().settings.icons.priority.clone();
+                    spans.push(Span::styled(icon, Style::default().fg(color)));
+                }
+                for tag in &item.tags {
+                    spans.push(Span::raw(" "));
+                    spans.push(Span::styled(
+                        format!(" #{} ", tag),
+                        Style::default()
+                            .fg(colors.foreground_dark)
+                            .bg(colors.tag_color(tag)),
+                    ));
+                }
+                let title_cell = Line::from(spans);
 
                 // Create row
                 let cell = Cell::from(title_cell);
@@ -403,7 +785,7 @@ impl Page for AllTasksPage {
         let list = Table::new(rows, &[Constraint::Percentage(100)]).block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Todos")
+                .title(self.get_title())
                 .border_style(border_style)
                 .border_type(border_type),
         );
@@ -7,6 +7,7 @@ use tui::widgets::Paragraph;
 
 use crate::ui::{AllTasksPage, DeleteTaskPage, InputMode, TaskPage, UIPage};
 
+#[allow(clippy::too_many_arguments)]
 pub fn render_bottom_bar(
     f: &mut Frame,
     area: Rect,
@@ -14,6 +15,7 @@ pub fn render_bottom_bar(
     task_page: &TaskPage,
     delete_task_page: &Option<DeleteTaskPage>,
     current_page: &UIPage,
+    notice: &Option<String>,
     chunks: &[Rect],
 ) {
     let colors = &all_tasks_page.app.borrow().settings.colors;
@@ -33,7 +35,8 @@ pub fn render_bottom_bar(
             },
             None => ("NORMAL", colors.normal_mode_color),
         },
-        UIPage::AllTasks => ("NORMAL", colors.normal_mode_color),
+        UIPage::AllTasks if all_tasks_page.searching => ("SEARCH", colors.command_mode_color),
+        UIPage::AllTasks | UIPage::Workspaces | UIPage::Tags => ("NORMAL", colors.normal_mode_color),
     };
 
     let left_margin = " ".repeat(chunks[0].x as usize);
@@ -42,7 +45,6 @@ pub fn render_bottom_bar(
     let now = Local::now().format("%H:%M").to_string();
 
     // Colors
-    let mode_color = mode_color;
     let neutral_dark = colors.neutral_dark;
     let neutral_light = colors.neutral_light;
     let fg_dark = colors.foreground_dark;
@@ -57,21 +59,34 @@ pub fn render_bottom_bar(
             .add_modifier(Modifier::BOLD),
     );
     let mode_arrow = Span::styled(powerline_r, Style::default().fg(mode_color).bg(neutral_light));
+    let workspace = all_tasks_page.app.borrow().workspace().to_string();
     let branch_section = Span::styled(
-        " main ",
+        format!(" {} ", workspace),
         Style::default()
             .fg(fg_light)
             .bg(neutral_light)
             .add_modifier(Modifier::BOLD),
     );
     let branch_arrow = Span::styled(powerline_r, Style::default().fg(neutral_light).bg(neutral_dark));
-    let left_line = Line::from(vec![
+    let mut left_line = Line::from(vec![
         Span::raw(left_margin),
         mode_section,
         mode_arrow,
         branch_section,
         branch_arrow,
     ]);
+    if all_tasks_page.searching {
+        let query = all_tasks_page.get_search_query().unwrap_or_default();
+        left_line.push_span(Span::styled(
+            format!(" /{}▏", query),
+            Style::default().fg(fg_light).bg(neutral_dark),
+        ));
+    } else if let Some(notice) = notice {
+        left_line.push_span(Span::styled(
+            format!(" {} ", notice),
+            Style::default().fg(colors.accent_color).bg(neutral_dark),
+        ));
+    }
 
     // Section (right)
     let time_section = Span::styled(
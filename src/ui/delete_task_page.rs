@@ -3,6 +3,7 @@ use crate::{
     configuration::KeyBindings,
     key,
 };
+use anyhow::Result;
 use std::{cell::RefCell, rc::Rc};
 use tui::{
     Frame,
@@ -43,8 +44,9 @@ impl DeleteTaskPage {
             .clone()
     }
 
-    pub fn remove_task(&self) {
-        self.app.borrow_mut().delete_task(self.task_id);
+    pub fn remove_task(&self) -> Result<()> {
+        self.app.borrow_mut().delete_task(self.task_id)?;
+        Ok(())
     }
 
     pub fn add_char(&mut self, c: char) {
@@ -57,8 +59,13 @@ impl DeleteTaskPage {
 
     pub fn submit(&mut self) -> bool {
         if self.task_form == self.get_task_name() {
-            self.remove_task();
-            true
+            match self.remove_task() {
+                Ok(()) => true,
+                Err(e) => {
+                    self.error = Some(format!("{:#}", e));
+                    false
+                }
+            }
         } else {
             self.error = Some(format!(
                 "The name you entered is not the same as the task name: '{}'",
@@ -68,7 +75,7 @@ impl DeleteTaskPage {
         }
     }
 
-    fn get_keybind_hint(&self) -> Line {
+    fn get_keybind_hint(&self) -> Line<'_> {
         let color = self.get_secondary_color();
         let kb = &self.app.borrow().settings.keybindings;
         let i = key!(kb.enter_insert_mode, color);
@@ -8,6 +8,7 @@ use crossterm::{
 use std::cell::RefCell;
 use std::io::stdout;
 use std::rc::Rc;
+use std::time::Duration;
 use tui::{
     Frame, Terminal,
     backend::{Backend, CrosstermBackend},
@@ -19,11 +20,15 @@ use tui::{
 mod all_tasks_page;
 mod bottombar;
 mod delete_task_page;
+mod tag_page;
 mod task_page;
+mod workspace_page;
 
 use all_tasks_page::AllTasksPage;
 use delete_task_page::DeleteTaskPage;
+use tag_page::TagPage;
 use task_page::TaskPage;
+use workspace_page::WorkspacePage;
 
 #[macro_export]
 macro_rules! key {
@@ -62,6 +67,8 @@ pub enum UIPage {
     NewTask,
     EditTask,
     DeleteTask,
+    Workspaces,
+    Tags,
 }
 
 #[derive(Eq, PartialEq)]
@@ -72,6 +79,9 @@ pub enum InputMode {
     Command,
 }
 
+/// How often the app checks for changes to the tasks and settings while waiting for input
+const TICK_RATE: Duration = Duration::from_millis(250);
+
 pub trait Page {
     fn ui(&self, f: &mut Frame, area: Rect, focused: bool);
 }
@@ -82,111 +92,280 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: App) -> Result<()> {
     let mut task_page = TaskPage::new(Rc::clone(&app));
     let mut current_page = UIPage::AllTasks;
     let mut delete_task_page = None;
+    let mut workspace_page = None;
+    let mut tag_page = None;
+    let mut notice = None;
 
     loop {
+        // Pick up changes made by other gyst processes, e.g. `gyst add` in another terminal
+        let refreshed = app.borrow_mut().store.refresh();
+        match refreshed {
+            Ok(true) => {
+                notice = Some("Tasks changed on disk, reloaded".to_string());
+                all_tasks_page.ensure_group_exists();
+                all_tasks_page.ensure_task_exists();
+                match current_page {
+                    UIPage::AllTasks => {
+                        if let Some(task_id) = all_tasks_page.current_id {
+                            task_page = TaskPage::new_from_task(Rc::clone(&app), task_id);
+                        }
+                    }
+                    UIPage::EditTask | UIPage::DeleteTask
+                        if all_tasks_page.current_id.is_none() =>
+                    {
+                        notice = Some("Task was removed on disk".to_string());
+                        current_page = UIPage::AllTasks;
+                        delete_task_page = None;
+                    }
+                    _ => {}
+                }
+            }
+            Ok(false) => {}
+            Err(e) => notice = Some(format!("Unable to reload tasks: {}", e)),
+        }
+
+        let reloaded = app.borrow_mut().reload_settings();
+        match reloaded {
+            Ok(true) => notice = Some("Settings changed on disk, reloaded".to_string()),
+            Ok(false) => {}
+            Err(e) => notice = Some(format!("Unable to reload settings: {:#}", e)),
+        }
+        if let Some(error) = all_tasks_page.take_save_error() {
+            notice = Some(error);
+        }
+
         terminal.draw(|f| {
             render_app(
                 f,
                 &mut all_tasks_page,
                 &mut task_page,
                 &mut delete_task_page,
+                &mut workspace_page,
+                &mut tag_page,
                 &current_page,
+                &notice,
             )
         })?;
         let keybindings = &app.borrow().settings.keybindings.clone();
 
+        // Wake up regularly to redraw and pick up changes, even without any input
+        if !event::poll(TICK_RATE)? {
+            continue;
+        }
         if let Event::Key(key) = event::read()? {
+            notice = None;
             let code = key.code;
             match current_page {
                 UIPage::AllTasks => match code {
-                    _ if code == keybindings.quit => break,
-                    _ if code == keybindings.down => {
+                    _ if all_tasks_page.completing.is_some() => {
+                        notice = all_tasks_page.answer_completing(code);
+                    }
+                    // Typing goes to the search until it's done or cancelled
+                    _ if all_tasks_page.searching => {
+                        match code {
+                            _ if key == keybindings.go_back => all_tasks_page.clear_search(),
+                            _ if key == keybindings.save_changes => all_tasks_page.finish_search(),
+                            KeyCode::Char(c) => all_tasks_page.add_search_char(c),
+                            KeyCode::Backspace => all_tasks_page.remove_search_char(),
+                            _ => {}
+                        }
+                        if let Some(task_id) = all_tasks_page.current_id {
+                            task_page = TaskPage::new_from_task(Rc::clone(&app), task_id);
+                        }
+                    }
+                    _ if key == keybindings.quit => break,
+                    _ if key == keybindings.search => all_tasks_page.start_search(),
+                    // While searching, these jump between the matches instead of adding a task
+                    _ if key == keybindings.next_match
+                        && all_tasks_page.get_search_query().is_some() =>
+                    {
+                        all_tasks_page.next_match();
+                        if let Some(task_id) = all_tasks_page.current_id {
+                            task_page = TaskPage::new_from_task(Rc::clone(&app), task_id);
+                        }
+                    }
+                    _ if key == keybindings.prev_match
+                        && all_tasks_page.get_search_query().is_some() =>
+                    {
+                        all_tasks_page.prev_match();
+                        if let Some(task_id) = all_tasks_page.current_id {
+                            task_page = TaskPage::new_from_task(Rc::clone(&app), task_id);
+                        }
+                    }
+                    _ if key == keybindings.go_back
+                        && all_tasks_page.get_search_query().is_some() =>
+                    {
+                        all_tasks_page.clear_search();
+                        if let Some(task_id) = all_tasks_page.current_id {
+                            task_page = TaskPage::new_from_task(Rc::clone(&app), task_id);
+                        }
+                    }
+                    _ if key == keybindings.down => {
                         all_tasks_page.next();
                         if let Some(task_id) = all_tasks_page.current_id {
                             task_page = TaskPage::new_from_task(Rc::clone(&app), task_id);
                         }
                     }
-                    _ if code == keybindings.up => {
+                    _ if key == keybindings.up => {
                         all_tasks_page.prev();
                         if let Some(task_id) = all_tasks_page.current_id {
                             task_page = TaskPage::new_from_task(Rc::clone(&app), task_id);
                         }
                     }
-                    _ if code == keybindings.complete_task => {
-                        all_tasks_page.toggle_selected();
+                    _ if key == keybindings.complete_task => {
+                        notice = all_tasks_page.toggle_selected();
+                    }
+                    _ if key == keybindings.toggle_subtasks => {
+                        all_tasks_page.toggle_collapsed();
                     }
-                    _ if code == keybindings.toggle_completed_tasks => {
+                    _ if key == keybindings.toggle_completed_tasks => {
                         all_tasks_page.toggle_hidden()
                     }
-                    _ if code == keybindings.delete_task => {
+                    _ if key == keybindings.toggle_deferred_tasks => {
+                        all_tasks_page.toggle_deferred()
+                    }
+                    _ if key == keybindings.bump_priority => {
+                        if let Err(e) = all_tasks_page.bump_selected_priority() {
+                            notice = Some(format!("{:#}", e));
+                        }
+                        if let Some(task_id) = all_tasks_page.current_id {
+                            task_page = TaskPage::new_from_task(Rc::clone(&app), task_id);
+                        }
+                    }
+                    _ if key == keybindings.delete_task => {
                         if let Some(task_id) = all_tasks_page.current_id {
                             delete_task_page = Some(DeleteTaskPage::new(Rc::clone(&app), task_id));
                             current_page = UIPage::DeleteTask;
                         }
                     }
-                    _ if code == keybindings.open_link => all_tasks_page.open_selected_link()?,
-                    _ if code == keybindings.new_task => {
+                    _ if key == keybindings.open_link => all_tasks_page.open_selected_link()?,
+                    _ if key == keybindings.new_task => {
                         current_page = UIPage::NewTask;
                         task_page = TaskPage::new(Rc::clone(&app));
                     }
-                    _ if code == keybindings.edit_task => {
-                        if all_tasks_page.current_id.is_some() {
-                            current_page = UIPage::EditTask;
+                    _ if key == keybindings.new_subtask => {
+                        if let Some(task_id) = all_tasks_page.current_id {
+                            current_page = UIPage::NewTask;
+                            task_page = TaskPage::new_subtask(Rc::clone(&app), task_id);
                         }
                     }
-                    _ if code == keybindings.next_group => {
+                    _ if key == keybindings.edit_task && all_tasks_page.current_id.is_some() => {
+                        current_page = UIPage::EditTask;
+                    }
+                    _ if key == keybindings.next_group => {
                         all_tasks_page.next_group();
                     }
-                    _ if code == keybindings.prev_group => {
+                    _ if key == keybindings.prev_group => {
                         all_tasks_page.prev_group();
                     }
+                    _ if key == keybindings.undo || key == keybindings.redo => {
+                        let (done, result) = if key == keybindings.undo {
+                            ("Undid", app.borrow_mut().undo())
+                        } else {
+                            ("Redid", app.borrow_mut().redo())
+                        };
+                        notice = Some(match result {
+                            Ok(Some(action)) => format!("{} {}", done, action),
+                            Ok(None) => "Nothing to do".to_string(),
+                            Err(e) => format!("Unable to update history: {:#}", e),
+                        });
+                        all_tasks_page.ensure_group_exists();
+                        all_tasks_page.ensure_task_exists();
+                        if let Some(task_id) = all_tasks_page.current_id {
+                            task_page = TaskPage::new_from_task(Rc::clone(&app), task_id);
+                        }
+                    }
+                    _ if key == keybindings.switch_workspace => {
+                        workspace_page = Some(WorkspacePage::new(Rc::clone(&app)));
+                        current_page = UIPage::Workspaces;
+                    }
+                    _ if key == keybindings.filter_tags => {
+                        let current_tag = all_tasks_page.get_current_tag();
+                        tag_page = Some(TagPage::new(Rc::clone(&app), current_tag.as_deref()));
+                        current_page = UIPage::Tags;
+                    }
                     _ => {}
                 },
+                UIPage::Workspaces => {
+                    let wp = workspace_page.as_mut().unwrap();
+                    match code {
+                        _ if key == keybindings.quit => break,
+                        _ if key == keybindings.down => wp.next(),
+                        _ if key == keybindings.up => wp.prev(),
+                        _ if key == keybindings.go_back => {
+                            current_page = UIPage::AllTasks;
+                            workspace_page = None;
+                        }
+                        _ if key == keybindings.save_changes && wp.submit() => {
+                            all_tasks_page = AllTasksPage::new(Rc::clone(&app));
+                            task_page = TaskPage::new(Rc::clone(&app));
+                            current_page = UIPage::AllTasks;
+                            workspace_page = None;
+                        }
+                        _ => {}
+                    }
+                }
+                UIPage::Tags => {
+                    let tp = tag_page.as_mut().unwrap();
+                    match code {
+                        _ if key == keybindings.quit => break,
+                        _ if key == keybindings.down => tp.next(),
+                        _ if key == keybindings.up => tp.prev(),
+                        _ if key == keybindings.go_back => {
+                            current_page = UIPage::AllTasks;
+                            tag_page = None;
+                        }
+                        _ if key == keybindings.save_changes => {
+                            all_tasks_page.set_tag(tp.selected());
+                            current_page = UIPage::AllTasks;
+                            tag_page = None;
+                        }
+                        _ => {}
+                    }
+                }
                 UIPage::DeleteTask => {
                     let dtp = delete_task_page.as_mut().unwrap();
                     match dtp.input_mode {
                         InputMode::Normal => match key.code {
-                            _ if code == keybindings.quit => break,
-                            _ if code == keybindings.enter_insert_mode => {
+                            _ if key == keybindings.quit => break,
+                            _ if key == keybindings.enter_insert_mode => {
                                 dtp.input_mode = InputMode::Insert;
                             }
-                            _ if code == keybindings.go_back => {
+                            _ if key == keybindings.go_back => {
                                 current_page = UIPage::AllTasks;
                             }
-                            _ if code == keybindings.save_changes => {
-                                if dtp.submit() {
-                                    all_tasks_page.ensure_group_exists();
-                                    all_tasks_page.ensure_task_exists();
-                                    current_page = UIPage::AllTasks;
-                                    delete_task_page = None;
-                                }
+                            _ if key == keybindings.save_changes && dtp.submit() => {
+                                all_tasks_page.ensure_group_exists();
+                                all_tasks_page.ensure_task_exists();
+                                current_page = UIPage::AllTasks;
+                                delete_task_page = None;
                             }
                             _ => {}
                         },
                         InputMode::Insert => match key.code {
-                            _ if code == keybindings.enter_normal_mode => {
+                            _ if key == keybindings.enter_normal_mode => {
                                 dtp.input_mode = InputMode::Normal;
                             }
-                            _ if code == keybindings.save_changes => {
-                                if dtp.submit() {
-                                    all_tasks_page.ensure_group_exists();
-                                    all_tasks_page.ensure_task_exists();
-                                    current_page = UIPage::AllTasks;
-                                    delete_task_page = None;
-                                }
+                            _ if key == keybindings.save_changes && dtp.submit() => {
+                                all_tasks_page.ensure_group_exists();
+                                all_tasks_page.ensure_task_exists();
+                                current_page = UIPage::AllTasks;
+                                delete_task_page = None;
                             }
+                            // A failed save must not fall through to typing the key
+                            _ if key == keybindings.save_changes => {}
                             KeyCode::Char(c) => dtp.add_char(c),
                             KeyCode::Backspace => dtp.remove_char(),
                             _ => {}
                         },
                         InputMode::Visual => match key.code {
-                            _ if code == keybindings.enter_normal_mode => {
+                            _ if key == keybindings.enter_normal_mode => {
                                 dtp.input_mode = InputMode::Normal;
                             }
                             _ => {}
                         },
                         InputMode::Command => match key.code {
-                            _ if code == keybindings.enter_normal_mode => {
+                            _ if key == keybindings.enter_normal_mode => {
                                 dtp.input_mode = InputMode::Normal;
                             }
                             _ => {}
@@ -195,47 +374,45 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: App) -> Result<()> {
                 }
                 UIPage::NewTask | UIPage::EditTask => match task_page.input_mode {
                     InputMode::Normal => match key.code {
-                        _ if code == keybindings.down => task_page.next_field(),
-                        _ if code == keybindings.up => task_page.prev_field(),
-                        _ if code == keybindings.quit => break,
-                        _ if code == keybindings.enter_insert_mode => {
+                        _ if key == keybindings.down => task_page.next_field(),
+                        _ if key == keybindings.up => task_page.prev_field(),
+                        _ if key == keybindings.quit => break,
+                        _ if key == keybindings.enter_insert_mode => {
                             task_page.input_mode = InputMode::Insert;
                         }
-                        _ if code == keybindings.go_back => {
+                        _ if key == keybindings.go_back => {
                             current_page = UIPage::AllTasks;
                         }
-                        _ if code == keybindings.save_changes => {
-                            if task_page.submit() {
-                                all_tasks_page.ensure_group_exists();
-                                all_tasks_page.ensure_task_exists();
-                                current_page = UIPage::AllTasks;
-                            }
+                        _ if key == keybindings.save_changes && task_page.submit() => {
+                            all_tasks_page.ensure_group_exists();
+                            all_tasks_page.ensure_task_exists();
+                            current_page = UIPage::AllTasks;
                         }
                         _ => {}
                     },
                     InputMode::Insert => match key.code {
-                        _ if code == keybindings.enter_normal_mode => {
+                        _ if key == keybindings.enter_normal_mode => {
                             task_page.input_mode = InputMode::Normal;
                         }
-                        _ if code == keybindings.save_changes => {
-                            if task_page.submit() {
-                                all_tasks_page.ensure_group_exists();
-                                all_tasks_page.ensure_task_exists();
-                                current_page = UIPage::AllTasks;
-                            }
+                        _ if key == keybindings.save_changes && task_page.submit() => {
+                            all_tasks_page.ensure_group_exists();
+                            all_tasks_page.ensure_task_exists();
+                            current_page = UIPage::AllTasks;
                         }
+                        // A failed save must not fall through to typing the key
+                        _ if key == keybindings.save_changes => {}
                         KeyCode::Char(c) => task_page.add_char(c),
                         KeyCode::Backspace => task_page.remove_char(),
                         _ => {}
                     },
                     InputMode::Visual => match key.code {
-                        _ if code == keybindings.enter_normal_mode => {
+                        _ if key == keybindings.enter_normal_mode => {
                             task_page.input_mode = InputMode::Normal;
                         }
                         _ => {}
                     },
                     InputMode::Command => match key.code {
-                        _ if code == keybindings.enter_normal_mode => {
+                        _ if key == keybindings.enter_normal_mode => {
                             task_page.input_mode = InputMode::Normal;
                         }
                         _ => {}
@@ -248,12 +425,16 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: App) -> Result<()> {
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn render_app(
     f: &mut Frame,
     all_tasks_page: &mut AllTasksPage,
     task_page: &mut TaskPage,
     delete_task_page: &mut Option<DeleteTaskPage>,
+    workspace_page: &mut Option<WorkspacePage>,
+    tag_page: &mut Option<TagPage>,
     current_page: &UIPage,
+    notice: &Option<String>,
 ) {
     // Split vertically: main UI and 1-line mode bar at the bottom
     let vertical_chunks = Layout::default()
@@ -286,10 +467,18 @@ fn render_app(
             all_tasks_page.ui(f, chunks[0], false);
             delete_task_page.as_mut().unwrap().ui(f, chunks[1], true);
         }
+        UIPage::Workspaces => {
+            all_tasks_page.ui(f, chunks[0], false);
+            workspace_page.as_mut().unwrap().ui(f, chunks[1], true);
+        }
+        UIPage::Tags => {
+            all_tasks_page.ui(f, chunks[0], false);
+            tag_page.as_mut().unwrap().ui(f, chunks[1], true);
+        }
         UIPage::AllTasks => {
             all_tasks_page.ui(f, chunks[0], true);
             // Always show the right panel
-            if let Some(_) = all_tasks_page.current_id {
+            if all_tasks_page.current_id.is_some() {
                 task_page.ui(f, chunks[1], false);
             } else {
                 // Show blank page with ASCII art, vertically centered
@@ -335,6 +524,7 @@ fn render_app(
         task_page,
         delete_task_page,
         current_page,
+        notice,
         &chunks,
     );
 }
@@ -0,0 +1,150 @@
+use crate::{app::App, configuration::KeyBindings, key};
+use itertools::Itertools;
+use std::{cell::RefCell, rc::Rc};
+use tui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, Wrap},
+};
+
+use super::Page;
+
+/// Picks the tag to filter the tasks by, or none to show them all
+pub struct TagPage {
+    pub tags: Vec<String>,
+    pub current_idx: usize,
+    pub app: Rc<RefCell<App>>,
+}
+
+impl TagPage {
+    pub fn new(app: Rc<RefCell<App>>, current_tag: Option<&str>) -> Self {
+        let tags: Vec<String> = app
+            .borrow()
+            .tasks()
+            .values()
+            .flat_map(|t| t.tags.iter().cloned())
+            .sorted_by_key(|t| t.to_lowercase())
+            .dedup_by(|a, b| a.eq_ignore_ascii_case(b))
+            .collect();
+        // The first row is for showing every task
+        let current_idx = current_tag
+            .and_then(|tag| tags.iter().position(|t| t.eq_ignore_ascii_case(tag)))
+            .map_or(0, |idx| idx + 1);
+
+        Self {
+            tags,
+            current_idx,
+            app,
+        }
+    }
+
+    pub fn next(&mut self) {
+        if self.current_idx < self.tags.len() {
+            self.current_idx += 1;
+        }
+    }
+
+    pub fn prev(&mut self) {
+        if self.current_idx > 0 {
+            self.current_idx -= 1;
+        }
+    }
+
+    /// The selected tag, or none for all tags
+    pub fn selected(&self) -> Option<String> {
+        self.current_idx
+            .checked_sub(1)
+            .and_then(|idx| self.tags.get(idx).cloned())
+    }
+
+    fn get_keybind_hint(&self) -> Line<'_> {
+        let color = self.get_secondary_color();
+        let kb = &self.app.borrow().settings.keybindings;
+        let j = key!(kb.down, color);
+        let k = key!(kb.up, color);
+        let enter = key!(kb.save_changes, color);
+        let b = key!(kb.go_back, color);
+
+        Line::from(vec![
+            Span::raw("Press "),
+            k,
+            Span::raw(" and "),
+            j,
+            Span::raw(" to move up and down, "),
+            enter,
+            Span::raw(" to only show the tasks with the selected tag, and "),
+            b,
+            Span::raw(" to go back to the main screen."),
+        ])
+    }
+
+    pub fn get_primary_color(&self) -> Color {
+        self.app.borrow().settings.colors.primary_color
+    }
+
+    pub fn get_secondary_color(&self) -> Color {
+        self.app.borrow().settings.colors.secondary_color
+    }
+}
+
+impl Page for TagPage {
+    fn ui(&self, f: &mut Frame, area: Rect, focused: bool) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(2)
+            .constraints([Constraint::Length(3), Constraint::Min(3)].as_ref())
+            .split(area);
+
+        // Draw border around area
+        let border_style = match focused {
+            true => Style::default().fg(self.get_primary_color()),
+            false => Style::default(),
+        };
+        let border_type = match focused {
+            true => BorderType::Thick,
+            false => BorderType::Plain,
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Tags")
+            .border_style(border_style)
+            .border_type(border_type);
+        f.render_widget(block, area);
+
+        // Keybinds description paragraph
+        let keybinds = Paragraph::new(self.get_keybind_hint())
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        f.render_widget(keybinds, chunks[0]);
+
+        // Tag list
+        let colors = &self.app.borrow().settings.colors;
+        let all = Span::styled(" All tags ", Style::default().fg(Color::White));
+        let tags = self.tags.iter().map(|tag| {
+            Span::styled(
+                format!(" #{} ", tag),
+                Style::default()
+                    .fg(colors.foreground_dark)
+                    .bg(colors.tag_color(tag)),
+            )
+        });
+        let rows = std::iter::once(all)
+            .chain(tags)
+            .enumerate()
+            .map(|(idx, span)| {
+                let marker = if idx == self.current_idx { ">" } else { " " };
+                let marker = Span::styled(
+                    format!(" {} ", marker),
+                    Style::default()
+                        .fg(self.get_secondary_color())
+                        .add_modifier(Modifier::BOLD),
+                );
+                Row::new(vec![Cell::from(Line::from(vec![marker, span]))])
+            });
+        let list = Table::new(rows, &[Constraint::Percentage(100)])
+            .block(Block::default().borders(Borders::ALL));
+        f.render_widget(list, chunks[1]);
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{app::App, configuration::KeyBindings, key, task_form::TaskForm};
+use crate::{
+    app::App, configuration::KeyBindings, key, repeat::Repeat, stats::Stats, task::Task,
+    task_form::TaskForm,
+};
+use chrono::Local;
+use itertools::Itertools;
 use std::{cell::RefCell, rc::Rc};
 use tui::{
     Frame,
@@ -28,12 +33,24 @@ impl TaskPage {
             input_mode: InputMode::Normal,
             current_idx: 0,
             error: None,
-            num_fields: 6,
+            num_fields: 10,
             editing_task: None,
             app,
         }
     }
 
+    /// A new subtask of the task with `parent_id`, in the same group
+    pub fn new_subtask(app: Rc<RefCell<App>>, parent_id: usize) -> TaskPage {
+        let group = app
+            .borrow()
+            .get_task(parent_id)
+            .and_then(|t| t.group.clone());
+        let mut task_page = TaskPage::new(app);
+        task_page.task_form.parent = Some(parent_id);
+        task_page.task_form.group = group.unwrap_or_default();
+        task_page
+    }
+
     pub fn new_from_task(app: Rc<RefCell<App>>, task_id: usize) -> TaskPage {
         let task = app.borrow().get_task(task_id).unwrap().clone();
         let task_form = TaskForm::from_task(&task, &app.borrow().settings);
@@ -43,7 +60,7 @@ impl TaskPage {
             input_mode: InputMode::Normal,
             current_idx: 0,
             error: None,
-            num_fields: 6,
+            num_fields: 10,
             editing_task: Some(task_id),
             app,
         }
@@ -65,10 +82,14 @@ impl TaskPage {
         match self.current_idx {
             0 => self.task_form.name.push(c),
             1 => self.task_form.date.push(c),
-            2 => self.task_form.repeats.push(c),
-            3 => self.task_form.group.push(c),
-            4 => self.task_form.description.push(c),
-            5 => self.task_form.url.push(c),
+            2 => self.task_form.start.push(c),
+            3 => self.task_form.repeats.push(c),
+            4 => self.task_form.priority.push(c),
+            5 => self.task_form.group.push(c),
+            6 => self.task_form.tags.push(c),
+            7 => self.task_form.blocked_by.push(c),
+            8 => self.task_form.description.push(c),
+            9 => self.task_form.url.push(c),
             _ => {}
         };
     }
@@ -77,10 +98,14 @@ impl TaskPage {
         match self.current_idx {
             0 => self.task_form.name.pop(),
             1 => self.task_form.date.pop(),
-            2 => self.task_form.repeats.pop(),
-            3 => self.task_form.group.pop(),
-            4 => self.task_form.description.pop(),
-            5 => self.task_form.url.pop(),
+            2 => self.task_form.start.pop(),
+            3 => self.task_form.repeats.pop(),
+            4 => self.task_form.priority.pop(),
+            5 => self.task_form.group.pop(),
+            6 => self.task_form.tags.pop(),
+            7 => self.task_form.blocked_by.pop(),
+            8 => self.task_form.description.pop(),
+            9 => self.task_form.url.pop(),
             _ => None,
         };
     }
@@ -89,16 +114,17 @@ impl TaskPage {
         let mut app = self.app.borrow_mut();
         let settings = &app.settings;
         let form_result = self.task_form.submit(settings);
-        match form_result {
-            Ok(new_task) => {
-                if let Some(task_id) = self.editing_task {
-                    app.delete_task(task_id);
-                }
-                app.add_task(new_task);
-                true
+        let result = form_result.and_then(|new_task| {
+            if self.editing_task.is_some() {
+                app.update_task(new_task)
+            } else {
+                app.add_task(new_task)
             }
+        });
+        match result {
+            Ok(_) => true,
             Err(e) => {
-                self.error = Some(e.to_string());
+                self.error = Some(format!("{:#}", e));
                 false
             }
         }
@@ -127,10 +153,13 @@ impl TaskPage {
             .date_formats
             .input_datetime_hint
             .clone();
-        format!("{} or {}", date_hint, datetime_hint)
+        format!(
+            "{}, {}, e.g. tomorrow 9am, or empty for no date",
+            date_hint, datetime_hint
+        )
     }
 
-    fn get_keybind_hint(&self) -> Line {
+    fn get_keybind_hint(&self) -> Line<'_> {
         let color = self.get_secondary_color();
         let kb = &self.app.borrow().settings.keybindings;
         let i = key!(kb.enter_insert_mode, color);
@@ -160,6 +189,77 @@ impl TaskPage {
         ])
     }
 
+    /// The completion heatmap of the task being edited, if it repeats
+    fn get_history(&self, width: u16) -> Option<Paragraph<'_>> {
+        let app = self.app.borrow();
+        let task = app.get_task(self.editing_task?)?;
+        if task.repeats == Repeat::Never {
+            return None;
+        }
+        let stats = Stats::for_task(app.tasks(), self.editing_task?)?;
+
+        // Each week takes two columns, after the borders and the weekday labels
+        let weeks = (width.saturating_sub(6) / 2) as usize;
+        let days = ["M", "T", "W", "T", "F", "S", "S"];
+        let done = Style::default().fg(self.get_primary_color());
+        let missed = Style::default().fg(Color::DarkGray);
+        let lines: Vec<Line> = days
+            .iter()
+            .zip(stats.heatmap(Local::now().date_naive(), weeks))
+            .map(|(day, row)| {
+                let mut spans = vec![Span::raw(format!("{} ", day))];
+                spans.extend(row.into_iter().map(|count| match count {
+                    Some(0) => Span::styled("· ", missed),
+                    Some(_) => Span::styled("■ ", done),
+                    None => Span::raw("  "),
+                }));
+                Line::from(spans)
+            })
+            .collect();
+
+        let title = format!(
+            "History (streak {}, longest {})",
+            stats.current_streak, stats.longest_streak
+        );
+        Some(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)))
+    }
+
+    /// The tasks the edited task waits for, and the ones waiting for it
+    fn get_dependencies(&self) -> Option<Paragraph<'_>> {
+        let app = self.app.borrow();
+        let task = app.get_task(self.editing_task?)?;
+        let describe = |tasks: Vec<&Task>| {
+            if tasks.is_empty() {
+                return "nothing".to_string();
+            }
+            tasks
+                .iter()
+                .map(|t| match t.complete {
+                    true => format!("'{}' (done)", t.name),
+                    false => format!("'{}'", t.name),
+                })
+                .join(", ")
+        };
+        let blocked_by: Vec<&Task> = task
+            .blocked_by
+            .iter()
+            .filter_map(|&id| app.get_task(id))
+            .collect();
+        let blocking = app.dependents(task.id?);
+        if blocked_by.is_empty() && blocking.is_empty() {
+            return None;
+        }
+
+        let lines = vec![
+            Line::from(format!("Blocked by: {}", describe(blocked_by))),
+            Line::from(format!("Blocking: {}", describe(blocking))),
+        ];
+        Some(
+            Paragraph::new(lines)
+                .block(Block::default().borders(Borders::ALL).title("Dependencies")),
+        )
+    }
+
     pub fn get_primary_color(&self) -> Color {
         self.app.borrow().settings.colors.primary_color
     }
@@ -184,6 +284,12 @@ impl Page for TaskPage {
                     Constraint::Length(3),
                     Constraint::Length(3),
                     Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(4),
+                    Constraint::Length(9),
                 ]
                 .as_ref(),
             )
@@ -227,36 +333,72 @@ impl Page for TaskPage {
         );
         f.render_widget(input, chunks[2]);
 
+        // Start
+        let curr_text = Text::from(self.task_form.start.clone());
+        let input = Paragraph::new(curr_text).style(self.border_style(2)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Start (hidden until then, e.g. next month)"),
+        );
+        f.render_widget(input, chunks[3]);
+
         // Repeats
         let curr_text = Text::from(self.task_form.repeats.to_string());
         let input =
             Paragraph::new(curr_text)
-                .style(self.border_style(2))
+                .style(self.border_style(3))
                 .block(Block::default().borders(Borders::ALL).title(
-                "Repeats (Never | Daily | Weekly | Monthly | Yearly | Mon,Tue,Wed,Thu,Fri,Sat,Sun)",
+                "Repeats (e.g. Daily | Mon,Thu | every 2 weeks on mon,thu | every month on the last fri until 2027-01-01 | every 3 days from completion)",
             ));
-        f.render_widget(input, chunks[3]);
+        f.render_widget(input, chunks[4]);
+
+        // Priority
+        let curr_text = Text::from(self.task_form.priority.clone());
+        let input = Paragraph::new(curr_text).style(self.border_style(4)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Priority (None | Low | Medium | High | Urgent)"),
+        );
+        f.render_widget(input, chunks[5]);
 
         // Group
         let curr_text = Text::from(self.task_form.group.clone());
         let input = Paragraph::new(curr_text)
-            .style(self.border_style(3))
+            .style(self.border_style(5))
             .block(Block::default().borders(Borders::ALL).title("Group"));
-        f.render_widget(input, chunks[4]);
+        f.render_widget(input, chunks[6]);
+
+        // Tags
+        let curr_text = Text::from(self.task_form.tags.clone());
+        let input = Paragraph::new(curr_text).style(self.border_style(6)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Tags (e.g. home, errand, or +home in the name)"),
+        );
+        f.render_widget(input, chunks[7]);
+
+        // Blocked by
+        let curr_text = Text::from(self.task_form.blocked_by.clone());
+        let input = Paragraph::new(curr_text).style(self.border_style(7)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Blocked by (ids of the tasks to complete first, e.g. 3, 12)"),
+        );
+        f.render_widget(input, chunks[8]);
 
         // Description
         let curr_text = Text::from(self.task_form.description.clone());
         let input = Paragraph::new(curr_text)
-            .style(self.border_style(4))
+            .style(self.border_style(8))
             .block(Block::default().borders(Borders::ALL).title("Description"));
-        f.render_widget(input, chunks[5]);
+        f.render_widget(input, chunks[9]);
 
-        // Description
+        // URL
         let curr_text = Text::from(self.task_form.url.clone());
         let input = Paragraph::new(curr_text)
-            .style(self.border_style(5))
+            .style(self.border_style(9))
             .block(Block::default().borders(Borders::ALL).title("URL"));
-        f.render_widget(input, chunks[6]);
+        f.render_widget(input, chunks[10]);
 
         // Place cursor
         if focused {
@@ -270,31 +412,57 @@ impl Page for TaskPage {
                     chunks[2].y + 1,
                 )),
                 2 => f.set_cursor_position((
-                    chunks[3].x + self.task_form.repeats.width() as u16 + 1,
+                    chunks[3].x + self.task_form.start.width() as u16 + 1,
                     chunks[3].y + 1,
                 )),
                 3 => f.set_cursor_position((
-                    chunks[4].x + self.task_form.group.width() as u16 + 1,
+                    chunks[4].x + self.task_form.repeats.width() as u16 + 1,
                     chunks[4].y + 1,
                 )),
                 4 => f.set_cursor_position((
-                    chunks[5].x + self.task_form.description.width() as u16 + 1,
+                    chunks[5].x + self.task_form.priority.width() as u16 + 1,
                     chunks[5].y + 1,
                 )),
                 5 => f.set_cursor_position((
-                    chunks[6].x + self.task_form.url.width() as u16 + 1,
+                    chunks[6].x + self.task_form.group.width() as u16 + 1,
                     chunks[6].y + 1,
                 )),
+                6 => f.set_cursor_position((
+                    chunks[7].x + self.task_form.tags.width() as u16 + 1,
+                    chunks[7].y + 1,
+                )),
+                7 => f.set_cursor_position((
+                    chunks[8].x + self.task_form.blocked_by.width() as u16 + 1,
+                    chunks[8].y + 1,
+                )),
+                8 => f.set_cursor_position((
+                    chunks[9].x + self.task_form.description.width() as u16 + 1,
+                    chunks[9].y + 1,
+                )),
+                9 => f.set_cursor_position((
+                    chunks[10].x + self.task_form.url.width() as u16 + 1,
+                    chunks[10].y + 1,
+                )),
                 _ => {}
             }
         }
 
+        // Dependencies
+        if let Some(dependencies) = self.get_dependencies() {
+            f.render_widget(dependencies, chunks[11]);
+        }
+
         // Error message
         if let Some(error) = &self.error {
             let error = Paragraph::new(Text::from(error.to_owned()))
                 .style(Style::default().fg(Color::Red))
                 .block(Block::default().borders(Borders::ALL).title("Error"));
-            f.render_widget(error, chunks[7]);
+            f.render_widget(error, chunks[12]);
+        }
+
+        // Completion history
+        if let Some(history) = self.get_history(chunks[13].width) {
+            f.render_widget(history, chunks[13]);
         }
     }
 }
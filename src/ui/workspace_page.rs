@@ -0,0 +1,160 @@
+use crate::{app::App, configuration::KeyBindings, key, workspace};
+use std::{cell::RefCell, rc::Rc};
+use tui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, Wrap},
+};
+
+use super::Page;
+
+pub struct WorkspacePage {
+    pub workspaces: Vec<String>,
+    pub current_idx: usize,
+    pub error: Option<String>,
+    pub app: Rc<RefCell<App>>,
+}
+
+impl WorkspacePage {
+    pub fn new(app: Rc<RefCell<App>>) -> Self {
+        let (workspaces, error) = match workspace::list() {
+            Ok(workspaces) => (workspaces, None),
+            Err(e) => (vec![], Some(e.to_string())),
+        };
+        let current_idx = workspaces
+            .iter()
+            .position(|w| w == app.borrow().workspace())
+            .unwrap_or(0);
+
+        Self {
+            workspaces,
+            current_idx,
+            error,
+            app,
+        }
+    }
+
+    pub fn next(&mut self) {
+        if self.current_idx + 1 < self.workspaces.len() {
+            self.current_idx += 1;
+        }
+    }
+
+    pub fn prev(&mut self) {
+        if self.current_idx > 0 {
+            self.current_idx -= 1;
+        }
+    }
+
+    /// Switches to the selected workspace
+    pub fn submit(&mut self) -> bool {
+        let Some(name) = self.workspaces.get(self.current_idx) else {
+            return false;
+        };
+        match self.app.borrow_mut().switch_workspace(name) {
+            Ok(()) => true,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                false
+            }
+        }
+    }
+
+    fn get_keybind_hint(&self) -> Line<'_> {
+        let color = self.get_secondary_color();
+        let kb = &self.app.borrow().settings.keybindings;
+        let j = key!(kb.down, color);
+        let k = key!(kb.up, color);
+        let enter = key!(kb.save_changes, color);
+        let b = key!(kb.go_back, color);
+
+        Line::from(vec![
+            Span::raw("Press "),
+            k,
+            Span::raw(" and "),
+            j,
+            Span::raw(" to move up and down, "),
+            enter,
+            Span::raw(" to switch to the selected workspace, and "),
+            b,
+            Span::raw(" to go back to the main screen."),
+        ])
+    }
+
+    pub fn get_primary_color(&self) -> Color {
+        self.app.borrow().settings.colors.primary_color
+    }
+
+    pub fn get_secondary_color(&self) -> Color {
+        self.app.borrow().settings.colors.secondary_color
+    }
+}
+
+impl Page for WorkspacePage {
+    fn ui(&self, f: &mut Frame, area: Rect, focused: bool) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(2)
+            .constraints(
+                [
+                    Constraint::Length(3),
+                    Constraint::Min(3),
+                    Constraint::Length(3),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+
+        // Draw border around area
+        let border_style = match focused {
+            true => Style::default().fg(self.get_primary_color()),
+            false => Style::default(),
+        };
+        let border_type = match focused {
+            true => BorderType::Thick,
+            false => BorderType::Plain,
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Workspaces")
+            .border_style(border_style)
+            .border_type(border_type);
+        f.render_widget(block, area);
+
+        // Keybinds description paragraph
+        let keybinds = Paragraph::new(self.get_keybind_hint())
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        f.render_widget(keybinds, chunks[0]);
+
+        // Workspace list
+        let active = self.app.borrow().workspace().to_string();
+        let rows = self.workspaces.iter().enumerate().map(|(idx, name)| {
+            let marker = if *name == active { "*" } else { " " };
+            let style = if idx == self.current_idx {
+                Style::default()
+                    .fg(self.get_secondary_color())
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            Row::new(vec![Cell::from(Span::styled(
+                format!(" {} {}", marker, name),
+                style,
+            ))])
+        });
+        let list = Table::new(rows, &[Constraint::Percentage(100)])
+            .block(Block::default().borders(Borders::ALL));
+        f.render_widget(list, chunks[1]);
+
+        // Error message
+        if let Some(error) = &self.error {
+            let error = Paragraph::new(Text::from(error.to_owned()))
+                .style(Style::default().fg(Color::Red))
+                .block(Block::default().borders(Borders::ALL).title("Error"));
+            f.render_widget(error, chunks[2]);
+        }
+    }
+}
@@ -1,74 +1,553 @@
-use anyhow::{Result, anyhow};
-use chrono::{DateTime, Local, NaiveDate, TimeZone, Timelike};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
 
-use crate::app::{App, Id};
+use crate::app::Id;
 use crate::configuration::Settings;
+use crate::date_parser;
+use crate::due::{self, Due};
+use crate::error::LoadError;
 use crate::task::Task;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
 
-pub fn load_tasks(file: PathBuf) -> HashMap<Id, Task> {
-    let file = fs::read_to_string(file).expect("Unable to read file");
-    let tasks_map: HashMap<Id, Task> =
-        serde_json::from_str(&file).expect("Unable to parse database file");
-    tasks_map
+const BACKUP_PREFIX: &str = "tasks-";
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// The version of the task database written by this build. Bump it and append a migration to
+/// `MIGRATIONS` whenever the stored format of a task changes.
+pub const SCHEMA_VERSION: u32 = 14;
+
+/// Upgrades a database from the version matching its index in `MIGRATIONS` to the next one
+type Migration = fn(Value) -> Result<Value>;
+
+const MIGRATIONS: &[Migration] = &[
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+    migrate_v5_to_v6,
+    migrate_v6_to_v7,
+    migrate_v7_to_v8,
+    migrate_v8_to_v9,
+    migrate_v9_to_v10,
+    migrate_v10_to_v11,
+    migrate_v11_to_v12,
+    migrate_v12_to_v13,
+    migrate_v13_to_v14,
+];
+
+/// The top level of the task database file
+#[derive(Serialize, Deserialize)]
+pub struct Database<T> {
+    pub schema_version: u32,
+    pub tasks: T,
 }
 
-pub fn save_tasks(file: PathBuf, app: &App) {
-    let file = fs::File::create(file).expect("Unable to create file");
-    serde_json::to_writer(file, &app.tasks).expect("Unable to write file");
+/// Version 0 was a bare map of ids to tasks
+fn migrate_v0_to_v1(tasks: Value) -> Result<Value> {
+    Ok(json!({ "schema_version": 1, "tasks": tasks }))
 }
 
-pub fn save_settings(file: &PathBuf, settings: &Settings) {
-    let file = fs::File::create(file).expect("Unable to create file");
-    serde_json::to_writer(file, &settings).expect("Unable to write file");
+/// Version 2 gave every repeating task a series id shared by all its occurrences
+fn migrate_v1_to_v2(mut db: Value) -> Result<Value> {
+    for (id, task) in tasks_mut(&mut db)? {
+        let task = task
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("Invalid task with id {}", id))?;
+        let repeats = task.get("repeats").is_some_and(|r| r != "Never");
+        let series = repeats.then(|| Uuid::new_v4().to_string());
+        task.insert("series".to_string(), json!(series));
+    }
+    db["schema_version"] = json!(2);
+    Ok(db)
 }
 
-pub fn date_has_time(date: &DateTime<Local>) -> bool {
-    let time = date.time();
-    if time.hour() == 23 && time.minute() == 59 {
-        return false;
+/// Version 3 records when each task was completed. Older completions have no known time.
+fn migrate_v2_to_v3(mut db: Value) -> Result<Value> {
+    for (id, task) in tasks_mut(&mut db)? {
+        let task = task
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("Invalid task with id {}", id))?;
+        task.insert("completed_at".to_string(), Value::Null);
     }
-    true
+    db["schema_version"] = json!(3);
+    Ok(db)
 }
 
-pub fn date_to_display_str(dt: &DateTime<Local>, settings: &Settings) -> String {
-    let format = if date_has_time(dt) {
-        settings.date_formats.display_datetime_format.clone()
-    } else {
-        settings.date_formats.display_date_format.clone()
+/// Version 4 replaced the fixed repeat options with rules that have an interval, days of the
+/// week or of the month and an end condition
+fn migrate_v3_to_v4(mut db: Value) -> Result<Value> {
+    let rule = |frequency: &str, days: Value| {
+        json!({ "Every": {
+            "frequency": frequency,
+            "interval": 1,
+            "days": days,
+            "month_day": null,
+            "ordinal": null,
+            "end": null,
+        }})
+    };
+
+    for (id, task) in tasks_mut(&mut db)? {
+        let repeats = task
+            .get_mut("repeats")
+            .ok_or_else(|| anyhow!("Task with id {} has no repeats", id))?;
+        let new_repeats = match repeats.as_str() {
+            Some("Never") => json!("Never"),
+            Some(frequency @ ("Daily" | "Weekly" | "Monthly" | "Yearly")) => {
+                rule(frequency, json!([]))
+            }
+            _ => match repeats.get("DaysOfWeek") {
+                Some(days) => rule("Weekly", days.clone()),
+                None => {
+                    return Err(anyhow!(
+                        "Task with id {} has invalid repeats: {}",
+                        id,
+                        repeats
+                    ));
+                }
+            },
+        };
+        *repeats = new_repeats;
+    }
+    db["schema_version"] = json!(4);
+    Ok(db)
+}
+
+/// Version 5 lets repeating tasks count from their completion and skip missed occurrences
+fn migrate_v4_to_v5(mut db: Value) -> Result<Value> {
+    for (_, task) in tasks_mut(&mut db)? {
+        if let Some(rule) = task
+            .pointer_mut("/repeats/Every")
+            .and_then(Value::as_object_mut)
+        {
+            rule.insert("anchor".to_string(), json!("Due"));
+            rule.insert("catch_up".to_string(), json!(false));
+        }
+    }
+    db["schema_version"] = json!(5);
+    Ok(db)
+}
+
+/// Version 6 tells tasks due on a day apart from tasks due at a time. Tasks due on a day used to
+/// be stored at 23:59:59, so that time is taken to mean the whole day.
+fn migrate_v5_to_v6(mut db: Value) -> Result<Value> {
+    for (id, task) in tasks_mut(&mut db)? {
+        let date = task
+            .get_mut("date")
+            .ok_or_else(|| anyhow!("Task with id {} has no date", id))?;
+        let datetime = date
+            .as_str()
+            .and_then(|d| DateTime::parse_from_str(d, "%+").ok())
+            .ok_or_else(|| anyhow!("Task with id {} has an invalid date: {}", id, date))?;
+        let end_of_day =
+            datetime.hour() == 23 && datetime.minute() == 59 && datetime.second() == 59;
+        *date = if end_of_day {
+            json!({ "Date": datetime.date_naive().to_string() })
+        } else {
+            json!({ "DateTime": date })
+        };
+    }
+    db["schema_version"] = json!(6);
+    Ok(db)
+}
+
+/// Version 7 stores the times tasks are due at as instants along with the time zone they were
+/// given in. Older tasks don't have one, so they follow the viewer's time zone.
+fn migrate_v6_to_v7(mut db: Value) -> Result<Value> {
+    for (_, task) in tasks_mut(&mut db)? {
+        if let Some(datetime) = task.pointer_mut("/date/DateTime") {
+            *datetime = json!({ "at": datetime, "zone": null });
+        }
+    }
+    db["schema_version"] = json!(7);
+    Ok(db)
+}
+
+/// Version 8 allows tasks without a date, which older versions can't read. Existing tasks keep
+/// their dates.
+fn migrate_v7_to_v8(mut db: Value) -> Result<Value> {
+    db["schema_version"] = json!(8);
+    Ok(db)
+}
+
+/// Version 9 lets tasks start on a later date than they're created. Existing tasks have
+/// already started.
+fn migrate_v8_to_v9(mut db: Value) -> Result<Value> {
+    for (id, task) in tasks_mut(&mut db)? {
+        let task = task
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("Invalid task with id {}", id))?;
+        task.insert("start".to_string(), Value::Null);
+    }
+    db["schema_version"] = json!(9);
+    Ok(db)
+}
+
+/// Version 10 gave tasks a priority, which existing tasks don't have
+fn migrate_v9_to_v10(mut db: Value) -> Result<Value> {
+    for (id, task) in tasks_mut(&mut db)? {
+        let task = task
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("Invalid task with id {}", id))?;
+        task.insert("priority".to_string(), json!("None"));
+    }
+    db["schema_version"] = json!(10);
+    Ok(db)
+}
+
+/// Version 11 gave tasks tags besides their group
+fn migrate_v10_to_v11(mut db: Value) -> Result<Value> {
+    for (id, task) in tasks_mut(&mut db)? {
+        let task = task
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("Invalid task with id {}", id))?;
+        task.insert("tags".to_string(), json!([]));
+    }
+    db["schema_version"] = json!(11);
+    Ok(db)
+}
+
+/// Version 12 lets tasks be subtasks of another one
+fn migrate_v11_to_v12(mut db: Value) -> Result<Value> {
+    for (id, task) in tasks_mut(&mut db)? {
+        let task = task
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("Invalid task with id {}", id))?;
+        task.insert("parent".to_string(), Value::Null);
+    }
+    db["schema_version"] = json!(12);
+    Ok(db)
+}
+
+/// Version 13 lets tasks wait for other tasks to be complete
+fn migrate_v12_to_v13(mut db: Value) -> Result<Value> {
+    for (id, task) in tasks_mut(&mut db)? {
+        let task = task
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("Invalid task with id {}", id))?;
+        task.insert("blocked_by".to_string(), json!([]));
+    }
+    db["schema_version"] = json!(13);
+    Ok(db)
+}
+
+/// Version 14 lets yearly rules on a day of the month be due in another month than the task.
+/// Existing rules stay in the month their task is due in.
+fn migrate_v13_to_v14(mut db: Value) -> Result<Value> {
+    for (_, task) in tasks_mut(&mut db)? {
+        if let Some(rule) = task
+            .pointer_mut("/repeats/Every")
+            .and_then(Value::as_object_mut)
+        {
+            rule.insert("month".to_string(), Value::Null);
+        }
+    }
+    db["schema_version"] = json!(14);
+    Ok(db)
+}
+
+/// The map of ids to tasks of a database from version 1 onwards
+fn tasks_mut(db: &mut Value) -> Result<&mut serde_json::Map<String, Value>> {
+    db.get_mut("tasks")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| anyhow!("Missing tasks"))
+}
+
+/// The version a database or journal was written at. Ones without a version are version 0.
+pub fn schema_version(db: &Value) -> Result<u32> {
+    match db.get("schema_version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| anyhow!("Invalid schema version: {}", version)),
+    }
+}
+
+/// Upgrades a database to `SCHEMA_VERSION`, one version at a time
+pub fn migrate(mut db: Value) -> Result<Value> {
+    let version = schema_version(&db)?;
+    if version > SCHEMA_VERSION {
+        return Err(anyhow!(
+            "Schema version {} is newer than the supported version {}, please upgrade gyst-tui",
+            version,
+            SCHEMA_VERSION
+        ));
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        db = migration(db)
+            .with_context(|| format!("Unable to migrate from version {} to {}", from, from + 1))?;
+    }
+    Ok(db)
+}
+
+/// Upgrades a single task written at `version` to `SCHEMA_VERSION`, e.g. a snapshot kept to
+/// undo a change
+pub fn migrate_task(task: Value, version: u32) -> Result<Value> {
+    let db = match version {
+        0 => json!({ "0": task }),
+        _ => json!({ "schema_version": version, "tasks": { "0": task } }),
     };
-    dt.format(format.as_str()).to_string()
+    let mut db = migrate(db)?;
+    db.pointer_mut("/tasks/0")
+        .map(Value::take)
+        .ok_or_else(|| anyhow!("Missing task"))
 }
 
-pub fn date_to_input_str(dt: &DateTime<Local>, settings: &Settings) -> String {
-    let format = if date_has_time(dt) {
-        settings.date_formats.input_datetime_format.clone()
+/// Refuses a database written by a newer gyst-tui, which can't be read but isn't corrupt
+pub fn ensure_supported(version: u32, file: &Path) -> Result<(), LoadError> {
+    if version > SCHEMA_VERSION {
+        return Err(LoadError::Newer {
+            path: file.to_path_buf(),
+            version,
+        });
+    }
+    Ok(())
+}
+
+/// Parses the contents of a task database of any known version
+pub fn parse_tasks(contents: &str, file: &Path) -> Result<HashMap<Id, Task>, LoadError> {
+    let db: Value = serde_json::from_str(contents).map_err(|e| LoadError::parse(file, e))?;
+    let version = schema_version(&db).map_err(|e| LoadError::schema(file, e))?;
+    ensure_supported(version, file)?;
+
+    let db: Database<HashMap<Id, Task>> = if version == SCHEMA_VERSION {
+        // Parse the text again to keep line numbers in errors
+        serde_json::from_str(contents).map_err(|e| LoadError::parse(file, e))?
     } else {
-        settings.date_formats.input_date_format.clone()
+        let db = migrate(db).map_err(|e| LoadError::schema(file, e))?;
+        serde_json::from_value(db).map_err(|e| LoadError::schema(file, e))?
     };
-    dt.format(format.as_str()).to_string()
+    Ok(db.tasks)
+}
+
+pub fn load_tasks(file: &Path) -> Result<HashMap<Id, Task>, LoadError> {
+    let contents = fs::read_to_string(file).map_err(|e| LoadError::io(file, e))?;
+    parse_tasks(&contents, file)
+}
+
+pub fn serialize_tasks(tasks: &HashMap<Id, Task>) -> Result<Vec<u8>> {
+    let db = Database {
+        schema_version: SCHEMA_VERSION,
+        tasks,
+    };
+    Ok(serde_json::to_vec(&db)?)
+}
+
+/// Saves the tasks to `file`, keeping up to `backups` copies of the previous contents in
+/// `backup_dir`.
+pub fn save_tasks(
+    file: &Path,
+    tasks: &HashMap<Id, Task>,
+    backup_dir: &Path,
+    backups: usize,
+) -> Result<()> {
+    if backups > 0 {
+        backup_file(file, backup_dir)?;
+        prune_backups(backup_dir, backups)?;
+    }
+    let contents = serialize_tasks(tasks)?;
+    atomic_write(file, &contents)
+}
+
+pub fn save_settings(file: &Path, settings: &Settings) -> Result<()> {
+    let contents = serde_json::to_vec(settings)?;
+    atomic_write(file, &contents)
+}
+
+/// `file` with `suffix` added to the end of its name, e.g. `work.tasks.lock` for `work.tasks`
+pub fn append_to_file_name(file: &Path, suffix: &str) -> PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    file.with_file_name(name)
+}
+
+/// Renames a file that could not be loaded to `<name>.corrupt-<timestamp>` so that a fresh one
+/// can be created in its place. Returns the new location of the file.
+pub fn move_aside(file: &Path) -> Result<PathBuf> {
+    let file_name = file
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid file path: {}", file.display()))?
+        .to_string_lossy();
+    let timestamp = Local::now().format(BACKUP_TIMESTAMP_FORMAT);
+    let new_path = file.with_file_name(format!("{}.corrupt-{}", file_name, timestamp));
+    fs::rename(file, &new_path)
+        .with_context(|| format!("Unable to move {} aside", file.display()))?;
+    Ok(new_path)
+}
+
+/// Replaces the contents of `file` without ever leaving it half written. The data is written
+/// to a temporary file in the same directory, flushed to disk and then renamed over `file`.
+pub fn atomic_write(file: &Path, contents: &[u8]) -> Result<()> {
+    let dir = file
+        .parent()
+        .ok_or_else(|| anyhow!("Invalid file path: {}", file.display()))?;
+    let file_name = file
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid file path: {}", file.display()))?
+        .to_string_lossy();
+    let tmp_path = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let write_tmp = || -> Result<()> {
+        let mut tmp = fs::File::create(&tmp_path)?;
+        tmp.write_all(contents)?;
+        tmp.sync_all()?;
+        Ok(())
+    };
+    if let Err(e) = write_tmp() {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e).with_context(|| format!("Unable to write {}", file.display()));
+    }
+
+    fs::rename(&tmp_path, file).with_context(|| format!("Unable to replace {}", file.display()))?;
+
+    // Make the rename itself durable. Directories can't be opened on every platform, in which
+    // case there is nothing more we can do.
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
 }
 
-pub fn get_today() -> DateTime<Local> {
-    let today = Local::now().date_naive().and_hms_opt(23, 59, 59).unwrap();
-    Local.from_local_datetime(&today).unwrap()
+/// Copies `file` into `backup_dir` under a timestamped name. Several saves within the same
+/// second keep the oldest copy, which is the state before the first of them.
+pub fn backup_file(file: &Path, backup_dir: &Path) -> Result<()> {
+    if !file.exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(backup_dir)?;
+
+    let timestamp = Local::now().format(BACKUP_TIMESTAMP_FORMAT);
+    let backup = backup_dir.join(format!("{}{}.json", BACKUP_PREFIX, timestamp));
+    if !backup.exists() {
+        let contents = fs::read(file)?;
+        atomic_write(&backup, &contents)?;
+    }
+    Ok(())
+}
+
+/// Returns the backups found in `backup_dir`, newest first.
+pub fn list_backups(backup_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !backup_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut backups: Vec<PathBuf> = fs::read_dir(backup_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .map(|n| n.to_string_lossy())
+                .map(|n| n.starts_with(BACKUP_PREFIX) && n.ends_with(".json"))
+                .unwrap_or(false)
+        })
+        .collect();
+    // Timestamps sort lexicographically
+    backups.sort();
+    backups.reverse();
+    Ok(backups)
+}
+
+pub fn backup_date(backup: &Path) -> Option<DateTime<Local>> {
+    let name = backup.file_stem()?.to_string_lossy();
+    // Skip any suffix, like the one added to pre-restore backups
+    let timestamp = name.strip_prefix(BACKUP_PREFIX)?.get(..15)?;
+    let naive = chrono::NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT).ok()?;
+    Local.from_local_datetime(&naive).single()
+}
+
+fn prune_backups(backup_dir: &Path, keep: usize) -> Result<()> {
+    for old in list_backups(backup_dir)?.into_iter().skip(keep) {
+        fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+/// Replaces `file` with the contents of `backup`. The current contents are backed up first so
+/// that a restore can itself be undone.
+pub fn restore_backup(backup: &Path, file: &Path, backup_dir: &Path) -> Result<()> {
+    let contents =
+        fs::read(backup).with_context(|| format!("Unable to read backup {}", backup.display()))?;
+    let text = String::from_utf8_lossy(&contents);
+    parse_tasks(&text, backup)?;
+
+    let current = fs::read(file).unwrap_or_default();
+    if current != contents {
+        let timestamp = Local::now().format(BACKUP_TIMESTAMP_FORMAT);
+        let name = format!("{}{}-pre-restore.json", BACKUP_PREFIX, timestamp);
+        atomic_write(&backup_dir.join(name), &current)?;
+    }
+    atomic_write(file, &contents)
+}
+
+pub fn date_to_display_str(due: &Due, settings: &Settings) -> String {
+    match due {
+        Due::Date(date) => date
+            .format(&settings.date_formats.display_date_format)
+            .to_string(),
+        Due::DateTime { at, .. } => datetime_to_display_str(&at.with_timezone(&Local), settings),
+    }
+}
+
+/// Formats a moment like a completion time, which always has a time of day
+pub fn datetime_to_display_str(dt: &DateTime<Local>, settings: &Settings) -> String {
+    dt.format(&settings.date_formats.display_datetime_format)
+        .to_string()
+}
+
+pub fn date_to_input_str(due: &Due, settings: &Settings) -> String {
+    match due {
+        Due::Date(date) => date
+            .format(&settings.date_formats.input_date_format)
+            .to_string(),
+        // Tasks kept to another time zone are edited in that time zone
+        Due::DateTime {
+            at,
+            zone: Some(zone),
+        } if Some(*zone) != due::local_zone() => format!(
+            "{} {}",
+            at.with_timezone(zone)
+                .format(&settings.date_formats.input_datetime_format),
+            zone.name()
+        ),
+        Due::DateTime { at, .. } => at
+            .with_timezone(&Local)
+            .format(&settings.date_formats.input_datetime_format)
+            .to_string(),
+    }
 }
 
-pub fn parse_date(s: &str, settings: &Settings) -> Result<DateTime<Local>> {
+/// Parses a date in one of the input formats of the settings, or anything `date_parser`
+/// understands like "tomorrow" or "next fri 9am"
+pub fn parse_date(s: &str, settings: &Settings) -> Result<Due> {
     let datetime_format = settings.date_formats.input_datetime_format.as_str();
     let date_format = settings.date_formats.input_date_format.as_str();
 
-    let attempt_datetime = Local.datetime_from_str(s, datetime_format);
+    let (without_zone, zone) = date_parser::split_zone(s);
+    let attempt_datetime = NaiveDateTime::parse_from_str(without_zone, datetime_format)
+        .ok()
+        .and_then(|datetime| Due::from_local(datetime, zone.or_else(due::local_zone)));
     let attempt_date = NaiveDate::parse_from_str(s, date_format);
 
-    if let Ok(datetime) = attempt_datetime {
-        Ok(datetime)
+    if let Some(due) = attempt_datetime {
+        Ok(due)
     } else if let Ok(date) = attempt_date {
-        let datetime = date.and_hms_opt(23, 59, 59).unwrap();
-        Ok(Local.from_local_datetime(&datetime).unwrap())
+        Ok(Due::Date(date))
     } else {
-        Err(anyhow!("Unable to parse date"))
+        date_parser::parse(s, Local::now()).map_err(|e| {
+            anyhow!(
+                "{}, use {}, {} or something like 'tomorrow' or 'next fri 9am'",
+                e,
+                settings.date_formats.input_date_hint,
+                settings.date_formats.input_datetime_hint
+            )
+        })
     }
 }
@@ -0,0 +1,78 @@
+use std::fs;
+
+use anyhow::{Result, anyhow};
+
+use crate::configuration::{SettingsBuilder, has_db_override};
+
+/// The workspace every installation starts with. It can't be removed.
+pub const DEFAULT_WORKSPACE: &str = "main";
+
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Invalid workspace name '{}', use only letters, numbers, '-' and '_'",
+            name
+        ))
+    }
+}
+
+/// A database given with `--db` or `GYST_DB` is a single list of todos, which every workspace
+/// would otherwise share
+pub fn ensure_available() -> Result<()> {
+    if has_db_override() {
+        Err(anyhow!(
+            "Workspaces can't be used with --db or GYST_DB, which give a single list of todos"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the names of all the workspaces, starting with the default one
+pub fn list() -> Result<Vec<String>> {
+    let mut workspaces = vec![];
+    let dir = SettingsBuilder::data_path()?.join("workspaces");
+    if dir.exists() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                workspaces.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    workspaces.retain(|w| w != DEFAULT_WORKSPACE && validate_name(w).is_ok());
+    workspaces.sort();
+    workspaces.insert(0, DEFAULT_WORKSPACE.to_string());
+    Ok(workspaces)
+}
+
+pub fn exists(name: &str) -> Result<bool> {
+    Ok(list()?.iter().any(|w| w == name))
+}
+
+pub fn create(name: &str) -> Result<()> {
+    validate_name(name)?;
+    if exists(name)? {
+        return Err(anyhow!("Workspace '{}' already exists", name));
+    }
+    fs::create_dir_all(SettingsBuilder::workspace_path(name)?)?;
+    Ok(())
+}
+
+/// Deletes a workspace along with all of its tasks
+pub fn remove(name: &str) -> Result<()> {
+    if name == DEFAULT_WORKSPACE {
+        return Err(anyhow!("The '{}' workspace can't be removed", name));
+    }
+    if !exists(name)? {
+        return Err(anyhow!("Workspace '{}' not found", name));
+    }
+    fs::remove_dir_all(SettingsBuilder::workspace_path(name)?)?;
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Local, TimeZone};
+
+use gyst_tui::utils;
+
+/// The names of the files in `dir`, sorted
+fn files(dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn atomic_writes_leave_no_temporary_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("tasks.json");
+
+    utils::atomic_write(&file, b"first").unwrap();
+    utils::atomic_write(&file, b"second").unwrap();
+    assert_eq!(fs::read(&file).unwrap(), b"second");
+    assert_eq!(files(dir.path()), vec!["tasks.json"]);
+
+    let missing = dir.path().join("missing").join("tasks.json");
+    assert!(utils::atomic_write(&missing, b"third").is_err());
+    assert_eq!(files(dir.path()), vec!["tasks.json"]);
+}
+
+#[test]
+fn backups_keep_the_first_version_of_each_second() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("tasks.json");
+    let backup_dir = dir.path().join("backups");
+
+    // There's nothing to back up before the first save
+    utils::backup_file(&file, &backup_dir).unwrap();
+    assert!(!backup_dir.exists());
+
+    fs::write(&file, b"first").unwrap();
+    utils::backup_file(&file, &backup_dir).unwrap();
+    fs::write(&file, b"second").unwrap();
+    utils::backup_file(&file, &backup_dir).unwrap();
+
+    let backups = utils::list_backups(&backup_dir).unwrap();
+    // Unless the clock ticked over to the next second in between
+    let oldest = backups.last().unwrap();
+    assert_eq!(fs::read(oldest).unwrap(), b"first");
+    assert!(backups.len() <= 2);
+}
+
+#[test]
+fn saving_rotates_the_backups() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("tasks.json");
+    let backup_dir = dir.path().join("backups");
+    fs::create_dir_all(&backup_dir).unwrap();
+    for day in 1..=4 {
+        let name = format!("tasks-2024010{}-120000.json", day);
+        fs::write(backup_dir.join(name), b"{}").unwrap();
+    }
+    fs::write(backup_dir.join("notes.txt"), b"not a backup").unwrap();
+
+    fs::write(&file, b"before").unwrap();
+    utils::save_tasks(&file, &HashMap::new(), &backup_dir, 3).unwrap();
+
+    let backups = utils::list_backups(&backup_dir).unwrap();
+    assert_eq!(backups.len(), 3);
+    assert_eq!(fs::read(&backups[0]).unwrap(), b"before");
+    let kept: Vec<_> = backups[1..]
+        .iter()
+        .map(|b| b.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(
+        kept,
+        vec!["tasks-20240104-120000.json", "tasks-20240103-120000.json"]
+    );
+    assert!(backup_dir.join("notes.txt").exists());
+}
+
+#[test]
+fn saving_without_backups_keeps_none() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("tasks.json");
+    let backup_dir = dir.path().join("backups");
+
+    fs::write(&file, b"before").unwrap();
+    utils::save_tasks(&file, &HashMap::new(), &backup_dir, 0).unwrap();
+    assert_ne!(fs::read(&file).unwrap(), b"before");
+    assert!(!backup_dir.exists());
+}
+
+#[test]
+fn restoring_backs_up_the_current_tasks() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("tasks.json");
+    let backup_dir = dir.path().join("backups");
+    fs::create_dir_all(&backup_dir).unwrap();
+    let backup = backup_dir.join("tasks-20240101-120000.json");
+    utils::save_tasks(&backup, &HashMap::new(), &backup_dir, 0).unwrap();
+    fs::write(&file, b"current").unwrap();
+
+    utils::restore_backup(&backup, &file, &backup_dir).unwrap();
+
+    assert_eq!(fs::read(&file).unwrap(), fs::read(&backup).unwrap());
+    let pre_restore = utils::list_backups(&backup_dir)
+        .unwrap()
+        .into_iter()
+        .find(|b| b.to_string_lossy().ends_with("-pre-restore.json"))
+        .unwrap();
+    assert_eq!(fs::read(&pre_restore).unwrap(), b"current");
+    assert!(utils::backup_date(&pre_restore).is_some());
+}
+
+#[test]
+fn invalid_backups_are_not_restored() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("tasks.json");
+    let backup_dir = dir.path().join("backups");
+    fs::create_dir_all(&backup_dir).unwrap();
+    let backup = backup_dir.join("tasks-20240101-120000.json");
+    fs::write(&backup, b"not json").unwrap();
+    fs::write(&file, b"current").unwrap();
+
+    assert!(utils::restore_backup(&backup, &file, &backup_dir).is_err());
+    assert_eq!(fs::read(&file).unwrap(), b"current");
+    assert_eq!(utils::list_backups(&backup_dir).unwrap(), vec![backup]);
+}
+
+#[test]
+fn backup_dates_are_read_from_their_names() {
+    let date = Local.with_ymd_and_hms(2024, 1, 2, 12, 30, 5).unwrap();
+    let plain = Path::new("tasks-20240102-123005.json");
+    let pre_restore = Path::new("tasks-20240102-123005-pre-restore.json");
+    assert_eq!(utils::backup_date(plain), Some(date));
+    assert_eq!(utils::backup_date(pre_restore), Some(date));
+    assert_eq!(utils::backup_date(Path::new("notes.json")), None);
+}
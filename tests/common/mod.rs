@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use gyst_tui::app::App;
+use gyst_tui::configuration::Settings;
+use gyst_tui::history::History;
+use gyst_tui::store::JsonStore;
+use gyst_tui::task::Task;
+use gyst_tui::utils;
+
+/// An app on an empty database in `dir`, with no history yet
+pub fn app_in(dir: &Path) -> App {
+    let db = dir.join("tasks.json");
+    utils::save_tasks(&db, &HashMap::new(), dir, 0).unwrap();
+    let store = JsonStore::open(&db, &dir.join("backups"), 0).unwrap();
+    App::with_store(Settings::default(), Box::new(store), History::for_db(&db))
+}
+
+/// Adds a task waiting for the tasks with the ids in `blocked_by`
+pub fn add(app: &mut App, name: &str, blocked_by: Vec<usize>) -> anyhow::Result<usize> {
+    let mut task = Task::default();
+    task.set_name(name.to_string());
+    task.blocked_by = blocked_by;
+    app.add_task(task)
+}
@@ -0,0 +1,106 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+
+use gyst_tui::date_parser;
+use gyst_tui::due::Due;
+
+/// Friday, October 16th 2026 at 10:15
+fn now() -> DateTime<Local> {
+    Local.with_ymd_and_hms(2026, 10, 16, 10, 15, 30).unwrap()
+}
+
+fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> Due {
+    Due::at(
+        Local
+            .with_ymd_and_hms(year, month, day, hour, minute, second)
+            .unwrap(),
+    )
+}
+
+fn on(year: i32, month: u32, day: u32) -> Due {
+    Due::Date(NaiveDate::from_ymd_opt(year, month, day).unwrap())
+}
+
+fn parse(input: &str) -> Due {
+    date_parser::parse(input, now()).unwrap()
+}
+
+#[test]
+fn days_have_no_time() {
+    assert_eq!(parse("today"), on(2026, 10, 16));
+    assert_eq!(parse("eod"), on(2026, 10, 16));
+    assert_eq!(parse("Tomorrow"), on(2026, 10, 17));
+    assert_eq!(parse("yesterday"), on(2026, 10, 15));
+    assert_eq!(parse("in 3 days"), on(2026, 10, 19));
+    assert_eq!(parse("in 1 week"), on(2026, 10, 23));
+    assert_eq!(parse("next month"), on(2026, 11, 16));
+}
+
+#[test]
+fn weekdays_are_after_today() {
+    assert_eq!(parse("mon"), on(2026, 10, 19));
+    assert_eq!(parse("next fri"), on(2026, 10, 23));
+    assert_eq!(parse("friday"), on(2026, 10, 23));
+    assert_eq!(parse("sat"), on(2026, 10, 17));
+}
+
+#[test]
+fn ends_of_periods() {
+    assert_eq!(parse("eow"), on(2026, 10, 18));
+    assert_eq!(parse("end of month"), on(2026, 10, 31));
+    assert_eq!(parse("eoy"), on(2026, 12, 31));
+}
+
+#[test]
+fn times() {
+    assert_eq!(parse("mon 9am"), at(2026, 10, 19, 9, 0, 0));
+    assert_eq!(parse("tomorrow at 5:30pm"), at(2026, 10, 17, 17, 30, 0));
+    assert_eq!(parse("14:45"), at(2026, 10, 16, 14, 45, 0));
+    assert_eq!(parse("today noon"), at(2026, 10, 16, 12, 0, 0));
+    assert_eq!(parse("12am"), at(2026, 10, 16, 0, 0, 0));
+    assert_eq!(parse("in 2 hours"), at(2026, 10, 16, 12, 15, 0));
+}
+
+#[test]
+fn iso_8601() {
+    assert_eq!(parse("2026-12-01"), on(2026, 12, 1));
+    assert_eq!(parse("2026-12-01T08:30"), at(2026, 12, 1, 8, 30, 0));
+    assert_eq!(parse("2026-12-01 08:30:15"), at(2026, 12, 1, 8, 30, 15));
+    assert_eq!(
+        parse("2026-12-01T08:30:00Z"),
+        Due::at(
+            chrono::Utc
+                .with_ymd_and_hms(2026, 12, 1, 8, 30, 0)
+                .unwrap()
+                .with_timezone(&Local)
+        )
+    );
+}
+
+#[test]
+fn invalid_dates_are_errors() {
+    for input in [
+        "",
+        "blah",
+        "next blah",
+        "in x days",
+        "in 3 fortnights",
+        "13pm",
+        "2026-13-01",
+    ] {
+        assert!(date_parser::parse(input, now()).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn dates_too_far_away_are_errors() {
+    for input in [
+        "in 100000000 days",
+        "in 100000000 weeks",
+        "in 4000000000 weeks",
+        "in 4000000000 hours",
+        "in 4000000000 years",
+    ] {
+        let error = date_parser::parse(input, now()).unwrap_err();
+        assert_eq!(error.to_string(), "Date is out of range", "{}", input);
+    }
+}
@@ -0,0 +1,143 @@
+use std::process::Command;
+
+use gyst_tui::configuration::Settings;
+use gyst_tui::task::Task;
+use gyst_tui::task_form::TaskForm;
+
+mod common;
+use common::{add, app_in};
+
+#[test]
+fn blockers_are_read_from_the_form() {
+    let mut form = TaskForm {
+        name: "Paint the fence".to_string(),
+        blocked_by: "3, #12 7".to_string(),
+        ..TaskForm::default()
+    };
+    let task = form.submit(&Settings::default()).unwrap();
+    assert_eq!(task.blocked_by, vec![3, 12, 7]);
+}
+
+#[test]
+fn invalid_blockers_are_rejected() {
+    let mut form = TaskForm {
+        name: "Paint the fence".to_string(),
+        blocked_by: "3, fence".to_string(),
+        ..TaskForm::default()
+    };
+    let error = form.submit(&Settings::default()).unwrap_err();
+    assert_eq!(error.to_string(), "Invalid task id 'fence'");
+}
+
+#[test]
+fn blockers_are_kept_when_editing() {
+    let mut task = Task::default();
+    task.set_name("Paint the fence".to_string());
+    task.set_blocked_by(vec![4, 9]);
+
+    let settings = Settings::default();
+    let mut form = TaskForm::from_task(&task, &settings);
+    assert_eq!(form.blocked_by, "4, 9");
+    assert_eq!(form.submit(&settings).unwrap().blocked_by, vec![4, 9]);
+}
+
+#[test]
+fn tasks_can_not_wait_for_themselves() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = app_in(dir.path());
+    let id = add(&mut app, "Paint the fence", vec![]).unwrap();
+
+    let mut task = app.get_task(id).unwrap().clone();
+    task.blocked_by = vec![id];
+    let error = app.update_task(task).unwrap_err();
+    assert_eq!(error.to_string(), "'Paint the fence' can't wait for itself");
+    assert!(app.get_task(id).unwrap().blocked_by.is_empty());
+}
+
+#[test]
+fn tasks_can_not_wait_for_each_other() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = app_in(dir.path());
+    let paint = add(&mut app, "Paint the fence", vec![]).unwrap();
+    let buy = add(&mut app, "Buy paint", vec![paint]).unwrap();
+    let sand = add(&mut app, "Sand the fence", vec![buy]).unwrap();
+
+    // Paint the fence -> Sand the fence -> Buy paint -> Paint the fence
+    let mut task = app.get_task(paint).unwrap().clone();
+    task.blocked_by = vec![sand];
+    let error = app.update_task(task).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "'Paint the fence' can't wait for 'Sand the fence', which already waits for it"
+    );
+}
+
+#[test]
+fn blockers_have_to_exist() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = app_in(dir.path());
+
+    let error = add(&mut app, "Paint the fence", vec![42]).unwrap_err();
+    assert_eq!(error.to_string(), "Task with id 42 not found");
+    assert!(app.tasks().is_empty());
+}
+
+#[test]
+fn tasks_can_start_once_their_blockers_are_complete() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = app_in(dir.path());
+    let buy = add(&mut app, "Buy paint", vec![]).unwrap();
+    let sand = add(&mut app, "Sand the fence", vec![]).unwrap();
+    let paint = add(&mut app, "Paint the fence", vec![buy, sand]).unwrap();
+
+    let dependents: Vec<_> = app.dependents(buy).iter().map(|t| t.id).collect();
+    assert_eq!(dependents, vec![Some(paint)]);
+    assert!(app.is_blocked(app.get_task(paint).unwrap()));
+
+    app.set_complete(buy, true).unwrap();
+    assert!(app.is_blocked(app.get_task(paint).unwrap()));
+    app.set_complete(sand, true).unwrap();
+    assert!(!app.is_blocked(app.get_task(paint).unwrap()));
+}
+
+#[test]
+fn deleting_a_blocker_unblocks_its_dependents() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = app_in(dir.path());
+    let buy = add(&mut app, "Buy paint", vec![]).unwrap();
+    let sand = add(&mut app, "Sand the fence", vec![]).unwrap();
+    let paint = add(&mut app, "Paint the fence", vec![buy, sand]).unwrap();
+
+    app.delete_task(buy).unwrap();
+    assert_eq!(app.get_task(paint).unwrap().blocked_by, vec![sand]);
+    assert!(app.dependents(buy).is_empty());
+
+    // Undoing the deletion makes it wait again
+    app.undo().unwrap();
+    assert_eq!(app.get_task(paint).unwrap().blocked_by, vec![buy, sand]);
+}
+
+#[test]
+fn ls_shows_blocked_or_actionable_tasks() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("tasks.json");
+    let gyst = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_gyst-tui"))
+            .args(args)
+            .env("HOME", dir.path())
+            .env("GYST_DB", &db)
+            .env("GYST_CONFIG_DIR", dir.path().join("config"))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    gyst(&["add", "Buy paint"]);
+    gyst(&["add", "Paint the fence", "--blocked-by", "1"]);
+
+    let blocked = gyst(&["ls", "--blocked"]);
+    assert!(blocked.contains("Paint the fence") && !blocked.contains("Buy paint"));
+    let actionable = gyst(&["ls", "--actionable"]);
+    assert!(actionable.contains("Buy paint") && !actionable.contains("Paint the fence"));
+}
@@ -0,0 +1,20 @@
+use gyst_tui::configuration::Settings;
+use gyst_tui::task::Task;
+use gyst_tui::task_form::TaskForm;
+
+#[test]
+fn editing_a_completed_task_keeps_it_complete() {
+    let settings = Settings::default();
+    let mut task = Task::default();
+    task.set_name("Water the plants".to_string());
+    task.set_complete();
+    let completed_at = task.completed_at;
+    assert!(completed_at.is_some());
+
+    let mut form = TaskForm::from_task(&task, &settings);
+    form.name = "Water the cactus".to_string();
+    let edited = form.submit(&settings).unwrap();
+    assert_eq!(edited.name, "Water the cactus");
+    assert!(edited.complete);
+    assert_eq!(edited.completed_at, completed_at);
+}
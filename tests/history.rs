@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use gyst_tui::history::History;
+use gyst_tui::store::{JsonStore, TaskStore};
+use gyst_tui::task::Task;
+use gyst_tui::utils;
+
+mod common;
+use common::{add, app_in};
+
+/// An empty task database in `dir`, with `journal` as its undo history if given
+fn store_with_journal(dir: &Path, journal: Option<&str>) -> (JsonStore, History) {
+    let db = dir.join("tasks.json");
+    utils::save_tasks(&db, &HashMap::new(), dir, 0).unwrap();
+    if let Some(journal) = journal {
+        fs::write(dir.join("tasks.json.history"), journal).unwrap();
+    }
+    let store = JsonStore::open(&db, &dir.join("backups"), 0).unwrap();
+    (store, History::for_db(&db))
+}
+
+#[test]
+fn journals_without_a_version_are_migrated() {
+    let dir = tempfile::tempdir().unwrap();
+    // Written before the database had a version, when it was a bare map of ids to tasks
+    let journal = r#"{"undo":[{"description":"delete 'Pay rent'","changes":[
+        {"Delete":{"id":7,"name":"Pay rent","date":"2023-04-01T23:59:59-04:00",
+        "repeats":"Never","group":"Home","description":null,"url":null,"complete":false}}]}],
+        "redo":[]}"#;
+    let (mut store, history) = store_with_journal(dir.path(), Some(journal));
+
+    let action = history.undo(&mut store).unwrap().unwrap();
+    assert_eq!(action.description, "delete 'Pay rent'");
+    let task = store.get(7).unwrap();
+    assert_eq!(task.name, "Pay rent");
+    assert_eq!(task.group.as_deref(), Some("Home"));
+}
+
+#[test]
+fn unreadable_journals_are_moved_aside() {
+    let dir = tempfile::tempdir().unwrap();
+    let (mut store, history) = store_with_journal(dir.path(), Some("not a journal"));
+
+    assert!(history.undo(&mut store).is_err());
+    assert!(!dir.path().join("tasks.json.history").exists());
+    let moved = fs::read_dir(dir.path())
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .any(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .starts_with("tasks.json.history.corrupt-")
+        });
+    assert!(moved);
+
+    assert!(history.undo(&mut store).unwrap().is_none());
+}
+
+#[test]
+fn adding_is_undone_and_redone() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = app_in(dir.path());
+    let id = add(&mut app, "Pay rent", vec![]).unwrap();
+
+    assert_eq!(app.undo().unwrap().as_deref(), Some("add 'Pay rent'"));
+    assert!(app.tasks().is_empty());
+    assert_eq!(app.undo().unwrap(), None);
+
+    assert_eq!(app.redo().unwrap().as_deref(), Some("add 'Pay rent'"));
+    assert_eq!(app.get_task(id).unwrap().name, "Pay rent");
+    assert_eq!(app.redo().unwrap(), None);
+}
+
+#[test]
+fn deleting_is_undone_and_redone() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = app_in(dir.path());
+    let mut task = Task::default();
+    task.set_name("Pay rent".to_string());
+    task.set_group("Home".to_string());
+    let id = app.add_task(task).unwrap();
+    app.delete_task(id).unwrap();
+
+    assert_eq!(app.undo().unwrap().as_deref(), Some("delete 'Pay rent'"));
+    let task = app.get_task(id).unwrap();
+    assert_eq!(task.name, "Pay rent");
+    assert_eq!(task.group.as_deref(), Some("Home"));
+
+    assert_eq!(app.redo().unwrap().as_deref(), Some("delete 'Pay rent'"));
+    assert!(app.get_task(id).is_none());
+}
+
+#[test]
+fn actions_made_of_several_changes_are_undone_at_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = app_in(dir.path());
+    let (rent, call) = app
+        .as_one_action("plan the week".to_string(), |app| {
+            let rent = add(app, "Pay rent", vec![])?;
+            let call = add(app, "Call mom", vec![])?;
+            app.set_complete(rent, true)?;
+            Ok((rent, call))
+        })
+        .unwrap();
+
+    assert_eq!(app.undo().unwrap().as_deref(), Some("plan the week"));
+    assert!(app.tasks().is_empty());
+    assert_eq!(app.undo().unwrap(), None);
+
+    assert_eq!(app.redo().unwrap().as_deref(), Some("plan the week"));
+    assert!(app.get_task(rent).unwrap().complete);
+    assert!(!app.get_task(call).unwrap().complete);
+}
+
+#[test]
+fn new_actions_clear_the_redo_history() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = app_in(dir.path());
+    add(&mut app, "Pay rent", vec![]).unwrap();
+    app.undo().unwrap();
+    add(&mut app, "Call mom", vec![]).unwrap();
+
+    assert_eq!(app.redo().unwrap(), None);
+    assert_eq!(app.tasks().len(), 1);
+}
+
+#[test]
+fn failing_to_save_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = app_in(dir.path());
+    let id = add(&mut app, "Pay rent", vec![]).unwrap();
+
+    // A file can't be written once a directory is in its place
+    let replace_with_dir = |name: &str| {
+        fs::remove_file(dir.path().join(name)).unwrap();
+        fs::create_dir(dir.path().join(name)).unwrap();
+    };
+    replace_with_dir("tasks.json.history");
+    assert!(add(&mut app, "Call mom", vec![]).is_err());
+    // Only the history is missing, the task itself is saved
+    assert_eq!(app.tasks().len(), 2);
+
+    replace_with_dir("tasks.json");
+    assert!(app.set_complete(id, true).is_err());
+}
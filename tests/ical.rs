@@ -0,0 +1,107 @@
+use std::process::Command;
+
+/// The output of `gyst-tui ls --format ical` after adding the tasks with `adds`
+fn export(adds: &[&[&str]]) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("tasks.json");
+    let gyst = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_gyst-tui"))
+            .args(args)
+            .env("HOME", dir.path())
+            .env("GYST_DB", &db)
+            .env("GYST_CONFIG_DIR", dir.path().join("config"))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    for args in adds {
+        gyst(args);
+    }
+    gyst(&["ls", "--format", "ical"])
+}
+
+/// The properties of the first VTODO, unfolded
+fn vtodo(ical: &str) -> Vec<String> {
+    ical.replace("\r\n ", "")
+        .split("\r\n")
+        .skip_while(|line| *line != "BEGIN:VTODO")
+        .take_while(|line| *line != "END:VTODO")
+        .map(|line| line.to_string())
+        .collect()
+}
+
+fn property<'a>(vtodo: &'a [String], name: &str) -> Option<&'a str> {
+    vtodo
+        .iter()
+        .find(|line| {
+            line.starts_with(&format!("{}:", name)) || line.starts_with(&format!("{};", name))
+        })
+        .map(|line| line.as_str())
+}
+
+#[test]
+fn lines_are_folded_at_75_octets() {
+    let description = "Sand it, paint it, and let it dry ".repeat(5) + "— twice";
+    let ical = export(&[&["add", "Paint the fence", "--description", &description]]);
+
+    assert!(ical.ends_with("END:VCALENDAR\r\n"));
+    for line in ical.split("\r\n") {
+        assert!(line.len() <= 75, "{}", line);
+    }
+    let vtodo = vtodo(&ical);
+    let expected = format!("DESCRIPTION:{}", description.replace(',', "\\,"));
+    assert_eq!(property(&vtodo, "DESCRIPTION"), Some(expected.as_str()));
+}
+
+#[test]
+fn to_dos_have_a_timestamp() {
+    let vtodo = vtodo(&export(&[&["add", "Paint the fence"]]));
+    assert!(property(&vtodo, "DTSTAMP").is_some_and(|stamp| stamp.ends_with('Z')));
+    assert!(property(&vtodo, "DTSTART").is_none());
+    assert!(property(&vtodo, "RRULE").is_none());
+}
+
+#[test]
+fn repeating_to_dos_start_when_they_are_due() {
+    let vtodo = vtodo(&export(&[&[
+        "add",
+        "Water the plants",
+        "--date",
+        "16-10-2026",
+        "--repeats",
+        "every week until 2026-12-31",
+    ]]));
+    assert_eq!(property(&vtodo, "DUE"), Some("DUE;VALUE=DATE:20261016"));
+    assert_eq!(
+        property(&vtodo, "DTSTART"),
+        Some("DTSTART;VALUE=DATE:20261016")
+    );
+    assert_eq!(
+        property(&vtodo, "RRULE"),
+        Some("RRULE:FREQ=WEEKLY;UNTIL=20261231")
+    );
+}
+
+#[test]
+fn until_is_a_time_when_the_to_do_is_due_at_a_time() {
+    let vtodo = vtodo(&export(&[&[
+        "add",
+        "Stand-up",
+        "--date",
+        "16-10-2026 09:30",
+        "--repeats",
+        "every weekday until 2026-12-31",
+    ]]));
+    let start = property(&vtodo, "DTSTART").unwrap();
+    assert!(!start.contains("VALUE=DATE"), "{}", start);
+    let rrule = property(&vtodo, "RRULE").unwrap();
+    let until = rrule.split("UNTIL=").nth(1).unwrap();
+    assert!(
+        until.starts_with("2026123") || until.starts_with("20270101"),
+        "{}",
+        rrule
+    );
+    assert!(until.len() == 16 && until.ends_with('Z'), "{}", rrule);
+}
@@ -0,0 +1,23 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use gyst_tui::configuration::{KeyBinding, KeyBindings};
+
+#[test]
+fn key_bindings_round_trip() {
+    for keybindings in [KeyBindings::default(), KeyBindings::get_vi_default()] {
+        let saved = serde_json::to_string(&keybindings).unwrap();
+        let reloaded: KeyBindings = serde_json::from_str(&saved).unwrap();
+        assert_eq!(reloaded, keybindings);
+        assert_eq!(reloaded.prev_match, KeyBinding::new(KeyCode::Char('N')));
+        assert_ne!(reloaded.prev_match, reloaded.next_match);
+    }
+}
+
+#[test]
+fn named_keys_and_modifiers_ignore_case() {
+    let keybindings: KeyBindings =
+        serde_json::from_str(r#"{"go_back": "ESC", "redo": "CTRL-r", "undo": "Alt-U"}"#).unwrap();
+    assert_eq!(keybindings.go_back, KeyBinding::new(KeyCode::Esc));
+    assert_eq!(keybindings.redo, KeyBinding::ctrl(KeyCode::Char('r')));
+    assert_eq!(keybindings.undo.code, KeyCode::Char('U'));
+    assert_eq!(keybindings.undo.modifiers, KeyModifiers::ALT);
+}
@@ -0,0 +1,204 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDate, TimeZone, Utc};
+
+use gyst_tui::day_of_week::DayOfWeek;
+use gyst_tui::due::Due;
+use gyst_tui::error::LoadError;
+use gyst_tui::priority::Priority;
+use gyst_tui::repeat::{Anchor, Frequency, Repeat};
+use gyst_tui::task::Task;
+use gyst_tui::utils::{self, SCHEMA_VERSION};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+fn load_fixture(name: &str) -> String {
+    fs::read_to_string(fixture(name)).expect("Fixture should exist")
+}
+
+#[test]
+fn unversioned_map_is_read_as_version_zero() {
+    let path = fixture("v0_tasks.json");
+    let tasks = utils::parse_tasks(&load_fixture("v0_tasks.json"), &path).unwrap();
+
+    assert_eq!(tasks.len(), 3);
+    let homework = &tasks[&108];
+    assert_eq!(homework.name, "LF112 Homework");
+    assert_eq!(homework.group.as_deref(), Some("School"));
+    assert!(matches!(homework.repeats, Repeat::Every(ref rule) if rule.days.len() == 3));
+    assert_eq!(tasks[&114].repeats, Repeat::every(Frequency::Weekly));
+    assert!(tasks[&3].complete);
+    assert_eq!(
+        tasks[&3].description.as_deref(),
+        Some("Bring insurance card")
+    );
+}
+
+#[test]
+fn empty_unversioned_map() {
+    let path = fixture("v0_empty.json");
+    let tasks = utils::parse_tasks(&load_fixture("v0_empty.json"), &path).unwrap();
+    assert!(tasks.is_empty());
+}
+
+#[test]
+fn version_one() {
+    let path = fixture("v1_tasks.json");
+    let tasks = utils::parse_tasks(&load_fixture("v1_tasks.json"), &path).unwrap();
+
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[&7].name, "Pay rent");
+    assert_eq!(tasks[&7].repeats, Repeat::every(Frequency::Monthly));
+}
+
+#[test]
+fn migrated_database_round_trips() {
+    let path = fixture("v0_tasks.json");
+    let tasks = utils::parse_tasks(&load_fixture("v0_tasks.json"), &path).unwrap();
+
+    let saved = utils::serialize_tasks(&tasks).unwrap();
+    let saved = String::from_utf8(saved).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&saved).unwrap();
+    assert_eq!(value["schema_version"], SCHEMA_VERSION);
+
+    let reloaded = utils::parse_tasks(&saved, &path).unwrap();
+    assert_eq!(reloaded.len(), tasks.len());
+    for (id, task) in tasks {
+        assert_eq!(reloaded[&id].name, task.name);
+        assert_eq!(reloaded[&id].date, task.date);
+    }
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let path = Path::new("tasks.json");
+    let contents = format!(
+        r#"{{"schema_version":{},"tasks":{{}}}}"#,
+        SCHEMA_VERSION + 1
+    );
+    let err = utils::parse_tasks(&contents, path).unwrap_err();
+    assert!(matches!(err, LoadError::Newer { .. }));
+    assert!(!err.is_corrupt());
+}
+
+#[test]
+fn malformed_old_files_are_corrupt() {
+    let path = Path::new("tasks.json");
+    let contents = r#"{"7":{"id":7,"name":"Pay rent","date":"not a date","repeats":"Never",
+        "group":null,"description":null,"url":null,"complete":false}}"#;
+    let err = utils::parse_tasks(contents, path).unwrap_err();
+    assert!(matches!(err, LoadError::Schema { .. }), "{}", err);
+    assert!(err.is_corrupt());
+}
+
+#[test]
+fn repeating_tasks_get_a_series() {
+    let path = fixture("v0_tasks.json");
+    let tasks = utils::parse_tasks(&load_fixture("v0_tasks.json"), &path).unwrap();
+
+    assert!(tasks[&108].series.is_some());
+    assert!(tasks[&114].series.is_some());
+    assert_ne!(tasks[&108].series, tasks[&114].series);
+    assert_eq!(tasks[&3].series, None);
+}
+
+#[test]
+fn days_of_week_become_a_weekly_rule() {
+    let path = fixture("v0_tasks.json");
+    let tasks = utils::parse_tasks(&load_fixture("v0_tasks.json"), &path).unwrap();
+
+    let Repeat::Every(rule) = &tasks[&108].repeats else {
+        panic!("Task should repeat");
+    };
+    assert_eq!(rule.frequency, Frequency::Weekly);
+    assert_eq!(rule.interval, 1);
+    assert_eq!(
+        rule.days,
+        vec![DayOfWeek::Sunday, DayOfWeek::Tuesday, DayOfWeek::Thursday]
+    );
+    assert_eq!(tasks[&3].repeats, Repeat::Never);
+}
+
+#[test]
+fn rules_keep_counting_from_the_due_date() {
+    let path = fixture("v1_tasks.json");
+    let tasks = utils::parse_tasks(&load_fixture("v1_tasks.json"), &path).unwrap();
+
+    let Repeat::Every(rule) = &tasks[&7].repeats else {
+        panic!("Task should repeat");
+    };
+    assert_eq!(rule.anchor, Anchor::Due);
+    assert!(!rule.catch_up);
+}
+
+#[test]
+fn end_of_day_times_become_whole_days() {
+    let path = fixture("v0_tasks.json");
+    let tasks = utils::parse_tasks(&load_fixture("v0_tasks.json"), &path).unwrap();
+
+    assert_eq!(
+        tasks[&108].date,
+        Some(Due::Date(NaiveDate::from_ymd_opt(2023, 3, 16).unwrap()))
+    );
+    assert!(tasks[&3].date.is_some_and(|d| d.has_time()));
+}
+
+#[test]
+fn times_a_minute_before_midnight_are_kept() {
+    let path = fixture("v5_tasks.json");
+    let tasks = utils::parse_tasks(&load_fixture("v5_tasks.json"), &path).unwrap();
+
+    assert_eq!(
+        tasks[&1].date,
+        Some(Due::Date(NaiveDate::from_ymd_opt(2023, 3, 16).unwrap()))
+    );
+    let train = tasks[&2].date.unwrap();
+    assert!(train.has_time());
+    assert_eq!(
+        train.deadline().with_timezone(&Utc),
+        Utc.with_ymd_and_hms(2023, 3, 16, 23, 59, 0).unwrap()
+    );
+}
+
+#[test]
+fn times_become_instants_without_a_time_zone() {
+    let path = fixture("v0_tasks.json");
+    let tasks = utils::parse_tasks(&load_fixture("v0_tasks.json"), &path).unwrap();
+
+    let at = Utc.with_ymd_and_hms(2023, 3, 20, 13, 30, 0).unwrap();
+    assert_eq!(tasks[&3].date, Some(Due::DateTime { at, zone: None }));
+}
+
+/// Whether a task has the default value of a field
+type IsDefault = fn(&Task) -> bool;
+
+/// The fields added since the first version
+const NEW_FIELDS: &[(&str, IsDefault)] = &[
+    ("start", |t| t.start.is_none()),
+    ("priority", |t| t.priority == Priority::None),
+    ("tags", |t| t.tags.is_empty()),
+    ("parent", |t| t.parent.is_none()),
+    ("blocked_by", |t| t.blocked_by.is_empty()),
+    (
+        "repeats.month",
+        |t| !matches!(&t.repeats, Repeat::Every(rule) if rule.month.is_some()),
+    ),
+];
+
+#[test]
+fn existing_tasks_get_the_defaults_of_new_fields() {
+    for name in ["v0_tasks.json", "v1_tasks.json", "v5_tasks.json"] {
+        let tasks = utils::parse_tasks(&load_fixture(name), &fixture(name)).unwrap();
+        for (field, is_default) in NEW_FIELDS {
+            for task in tasks.values() {
+                assert!(is_default(task), "{} of '{}' in {}", field, task.name, name);
+            }
+        }
+    }
+}
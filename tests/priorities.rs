@@ -0,0 +1,58 @@
+use chrono::NaiveDate;
+
+use gyst_tui::due::Due;
+use gyst_tui::priority::Priority;
+use gyst_tui::task::Task;
+
+fn task(name: &str, day: u32, priority: Priority) -> Task {
+    let mut task = Task::default();
+    task.set_name(name.to_string());
+    task.set_date(Some(Due::Date(
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap(),
+    )));
+    task.set_priority(priority);
+    task
+}
+
+#[test]
+fn priorities_are_read_by_name_or_number() {
+    assert_eq!("".parse::<Priority>().unwrap(), Priority::None);
+    assert_eq!("med".parse::<Priority>().unwrap(), Priority::Medium);
+    assert_eq!("Urgent".parse::<Priority>().unwrap(), Priority::Urgent);
+    assert_eq!("3".parse::<Priority>().unwrap(), Priority::High);
+    assert!("critical".parse::<Priority>().is_err());
+}
+
+#[test]
+fn bumping_goes_back_to_none_after_urgent() {
+    let mut priority = Priority::None;
+    let mut seen = vec![];
+    for _ in 0..5 {
+        priority = priority.bump();
+        seen.push(priority);
+    }
+    assert_eq!(
+        seen,
+        vec![
+            Priority::Low,
+            Priority::Medium,
+            Priority::High,
+            Priority::Urgent,
+            Priority::None
+        ]
+    );
+}
+
+#[test]
+fn the_most_important_tasks_come_first_within_a_day() {
+    let mut tasks = [
+        task("later", 17, Priority::Urgent),
+        task("low", 16, Priority::Low),
+        task("urgent", 16, Priority::Urgent),
+        task("none", 16, Priority::None),
+    ];
+    tasks.sort_by(|a, b| a.cmp_day_and_priority(b));
+
+    let names: Vec<&str> = tasks.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["urgent", "low", "none", "later"]);
+}
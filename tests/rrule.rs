@@ -0,0 +1,352 @@
+use chrono::{Local, NaiveDate, TimeZone};
+
+use gyst_tui::due::Due;
+use gyst_tui::repeat::{End, Frequency, Repeat};
+use gyst_tui::task::Task;
+
+fn date(year: i32, month: u32, day: u32) -> Due {
+    Due::at(Local.with_ymd_and_hms(year, month, day, 9, 0, 0).unwrap())
+}
+
+/// The due dates of the occurrences after the first one, by completing each of them in turn
+fn next_dates(rrule: &str, start: Due, count: usize) -> Vec<NaiveDate> {
+    let mut task = Task::default();
+    task.set_date(Some(start));
+    task.set_repeats(Repeat::from_rrule(rrule).unwrap());
+
+    let mut dates = vec![];
+    while dates.len() < count {
+        match task.set_complete() {
+            Some(next) => {
+                let date = next.date.unwrap();
+                assert_eq!(date.time(), start.time());
+                dates.push(date.date_naive());
+                task = next;
+            }
+            None => break,
+        }
+    }
+    dates
+}
+
+fn days(dates: &[(i32, u32, u32)]) -> Vec<NaiveDate> {
+    dates
+        .iter()
+        .map(|&(y, m, d)| NaiveDate::from_ymd_opt(y, m, d).unwrap())
+        .collect()
+}
+
+#[test]
+fn daily() {
+    assert_eq!(
+        next_dates("FREQ=DAILY", date(2026, 2, 27), 3),
+        days(&[(2026, 2, 28), (2026, 3, 1), (2026, 3, 2)])
+    );
+}
+
+#[test]
+fn every_three_days() {
+    assert_eq!(
+        next_dates("RRULE:FREQ=DAILY;INTERVAL=3", date(2026, 1, 30), 3),
+        days(&[(2026, 2, 2), (2026, 2, 5), (2026, 2, 8)])
+    );
+}
+
+#[test]
+fn weekly() {
+    assert_eq!(
+        next_dates("FREQ=WEEKLY", date(2026, 12, 24), 2),
+        days(&[(2026, 12, 31), (2027, 1, 7)])
+    );
+}
+
+#[test]
+fn every_other_week_on_two_days() {
+    // Friday the 16th, so the rest of this week is skipped
+    assert_eq!(
+        next_dates("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH", date(2026, 10, 16), 4),
+        days(&[
+            (2026, 10, 26),
+            (2026, 10, 29),
+            (2026, 11, 9),
+            (2026, 11, 12)
+        ])
+    );
+}
+
+#[test]
+fn weekdays() {
+    assert_eq!(
+        next_dates("FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR", date(2026, 10, 15), 3),
+        days(&[(2026, 10, 16), (2026, 10, 19), (2026, 10, 20)])
+    );
+}
+
+#[test]
+fn monthly_on_the_15th() {
+    assert_eq!(
+        next_dates("FREQ=MONTHLY;BYMONTHDAY=15", date(2026, 11, 15), 3),
+        days(&[(2026, 12, 15), (2027, 1, 15), (2027, 2, 15)])
+    );
+}
+
+#[test]
+fn monthly_on_the_31st_skips_short_months() {
+    assert_eq!(
+        next_dates("FREQ=MONTHLY;BYMONTHDAY=31", date(2027, 1, 31), 3),
+        days(&[(2027, 3, 31), (2027, 5, 31), (2027, 7, 31)])
+    );
+}
+
+#[test]
+fn last_day_of_the_month() {
+    assert_eq!(
+        next_dates("FREQ=MONTHLY;BYMONTHDAY=-1", date(2028, 1, 31), 3),
+        days(&[(2028, 2, 29), (2028, 3, 31), (2028, 4, 30)])
+    );
+}
+
+#[test]
+fn last_friday_of_the_month() {
+    assert_eq!(
+        next_dates("FREQ=MONTHLY;BYDAY=-1FR", date(2026, 10, 30), 3),
+        days(&[(2026, 11, 27), (2026, 12, 25), (2027, 1, 29)])
+    );
+}
+
+#[test]
+fn second_tuesday_every_other_month() {
+    assert_eq!(
+        next_dates("FREQ=MONTHLY;INTERVAL=2;BYDAY=2TU", date(2026, 10, 13), 2),
+        days(&[(2026, 12, 8), (2027, 2, 9)])
+    );
+}
+
+#[test]
+fn first_weekday_of_the_month() {
+    assert_eq!(
+        next_dates(
+            "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=1",
+            date(2026, 10, 1),
+            3
+        ),
+        days(&[(2026, 11, 2), (2026, 12, 1), (2027, 1, 1)])
+    );
+}
+
+#[test]
+fn yearly_on_leap_day() {
+    assert_eq!(
+        next_dates("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=29", date(2028, 2, 29), 1),
+        days(&[(2032, 2, 29)])
+    );
+}
+
+#[test]
+fn yearly_on_a_day_of_another_month() {
+    // Thanksgiving, for a task first due in October
+    assert_eq!(
+        next_dates("FREQ=YEARLY;BYMONTH=11;BYDAY=4TH", date(2026, 10, 1), 3),
+        days(&[(2026, 11, 26), (2027, 11, 25), (2028, 11, 23)])
+    );
+}
+
+#[test]
+fn yearly_rrules_on_a_day_need_a_month() {
+    // Without BYMONTH these are the last friday of the year and the 15th of every month
+    for rrule in ["FREQ=YEARLY;BYDAY=-1FR", "FREQ=YEARLY;BYMONTHDAY=15"] {
+        assert!(Repeat::from_rrule(rrule).is_err(), "{}", rrule);
+    }
+}
+
+#[test]
+fn yearly_rules_on_a_day_export_the_month_they_are_due_in() {
+    let repeat = Repeat::parse_from_str("every year on the last fri").unwrap();
+    let due = date(2026, 11, 27);
+    assert_eq!(
+        repeat.to_rrule(Some(&due)).as_deref(),
+        Some("FREQ=YEARLY;BYMONTH=11;BYDAY=-1FR")
+    );
+    assert_eq!(repeat.to_rrule(None), None);
+
+    let repeat = Repeat::parse_from_str("every year on the 15th of march").unwrap();
+    assert_eq!(
+        repeat.to_rrule(Some(&due)).as_deref(),
+        Some("FREQ=YEARLY;BYMONTH=3;BYMONTHDAY=15")
+    );
+}
+
+#[test]
+fn tasks_due_on_a_day_stay_due_on_a_day() {
+    let start = Due::Date(NaiveDate::from_ymd_opt(2026, 10, 16).unwrap());
+    assert_eq!(
+        next_dates("FREQ=WEEKLY;BYDAY=MO,TH", start, 2),
+        days(&[(2026, 10, 19), (2026, 10, 22)])
+    );
+}
+
+#[test]
+fn count_limits_the_occurrences() {
+    // The first occurrence is the task itself
+    assert_eq!(
+        next_dates("FREQ=WEEKLY;COUNT=3", date(2026, 10, 16), 10),
+        days(&[(2026, 10, 23), (2026, 10, 30)])
+    );
+}
+
+#[test]
+fn until_limits_the_occurrences() {
+    assert_eq!(
+        next_dates("FREQ=DAILY;UNTIL=20261018T235959Z", date(2026, 10, 16), 10),
+        days(&[(2026, 10, 17), (2026, 10, 18)])
+    );
+}
+
+#[test]
+fn round_trips() {
+    let samples = [
+        "FREQ=DAILY",
+        "FREQ=DAILY;INTERVAL=3",
+        "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;UNTIL=20270101",
+        "FREQ=MONTHLY;BYMONTHDAY=15",
+        "FREQ=MONTHLY;BYMONTHDAY=-1",
+        "FREQ=MONTHLY;BYDAY=-1FR",
+        "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=1",
+        "FREQ=YEARLY;COUNT=5",
+        "FREQ=YEARLY;BYMONTH=11;BYDAY=4TH",
+        "FREQ=YEARLY;INTERVAL=2;BYMONTH=2;BYMONTHDAY=-1",
+    ];
+    for rrule in samples {
+        let repeat = Repeat::from_rrule(rrule).unwrap();
+        assert_eq!(repeat.to_rrule(None).as_deref(), Some(rrule));
+
+        // The readable form describes the same rule
+        let readable = repeat.to_string();
+        assert_eq!(
+            Repeat::parse_from_str(&readable).unwrap(),
+            repeat,
+            "{}",
+            readable
+        );
+    }
+}
+
+#[test]
+fn parse_from_str_accepts_rrules() {
+    let repeat = Repeat::parse_from_str("RRULE:FREQ=WEEKLY;BYDAY=MO,WE").unwrap();
+    assert_eq!(repeat, Repeat::parse_from_str("mon,wed").unwrap());
+
+    let Repeat::Every(rule) = Repeat::parse_from_str("freq=monthly;count=2").unwrap() else {
+        panic!("Should repeat");
+    };
+    assert_eq!(rule.frequency, Frequency::Monthly);
+    assert_eq!(rule.end, Some(End::Count(2)));
+}
+
+#[test]
+fn never_has_no_rrule() {
+    assert_eq!(Repeat::Never.to_rrule(None), None);
+}
+
+#[test]
+fn unsupported_rrules_are_rejected() {
+    for rrule in [
+        "FREQ=HOURLY",
+        "INTERVAL=2",
+        "FREQ=YEARLY;BYMONTH=3",
+        "FREQ=MONTHLY;BYDAY=1MO,-1FR",
+        "FREQ=WEEKLY;BYDAY=XX",
+    ] {
+        assert!(Repeat::from_rrule(rrule).is_err(), "{}", rrule);
+    }
+}
+
+#[test]
+fn days_that_are_not_weekdays_are_rejected() {
+    for rrule in [
+        "FREQ=WEEKLY;BYDAY=€",
+        "FREQ=WEEKLY;BYDAY=MO,€U",
+        "FREQ=MONTHLY;BYDAY=-1€",
+        "FREQ=MONTHLY;BYDAY=2",
+    ] {
+        let error = Repeat::from_rrule(rrule).unwrap_err();
+        assert!(error.to_string().starts_with("Invalid day"), "{}", rrule);
+    }
+}
+
+/// The due date after the occurrence due at `due` is completed at `completed`
+fn next_after(repeats: &str, due: Due, completed: Due) -> Option<Due> {
+    let repeat = Repeat::parse_from_str(repeats).unwrap();
+    let completed = completed.to_local().unwrap();
+    repeat.next(due, completed).map(|(next, _)| next)
+}
+
+#[test]
+fn repeats_count_from_the_due_date_by_default() {
+    // Completed two weeks late, the next one is still a week after the last due date
+    let next = next_after("weekly", date(2026, 3, 2), date(2026, 3, 18));
+    assert_eq!(next, Some(date(2026, 3, 9)));
+}
+
+#[test]
+fn repeats_can_count_from_completion() {
+    let late = next_after(
+        "every 3 days from completion",
+        date(2026, 3, 2),
+        date(2026, 3, 6),
+    );
+    assert_eq!(late, Some(date(2026, 3, 9)));
+
+    let early = next_after(
+        "every 3 days from completion",
+        date(2026, 3, 10),
+        date(2026, 3, 6),
+    );
+    assert_eq!(early, Some(date(2026, 3, 9)));
+}
+
+#[test]
+fn completing_several_periods_late_skips_the_missed_occurrences() {
+    let next = next_after(
+        "weekly skipping missed",
+        date(2026, 3, 2),
+        date(2026, 3, 18),
+    );
+    assert_eq!(next, Some(date(2026, 3, 23)));
+
+    // The occurrence due on the day of completion is the next one
+    let next = next_after(
+        "weekly skipping missed",
+        date(2026, 3, 2),
+        date(2026, 3, 16),
+    );
+    assert_eq!(next, Some(date(2026, 3, 16)));
+
+    // Completing on time doesn't skip anything
+    let next = next_after("weekly skipping missed", date(2026, 3, 2), date(2026, 3, 2));
+    assert_eq!(next, Some(date(2026, 3, 9)));
+}
+
+#[test]
+fn skipped_occurrences_count_towards_the_end() {
+    let next = next_after(
+        "weekly for 3 times skipping missed",
+        date(2026, 3, 2),
+        date(2026, 3, 10),
+    );
+    assert_eq!(next, Some(date(2026, 3, 16)));
+
+    let next = next_after(
+        "weekly for 3 times skipping missed",
+        date(2026, 3, 2),
+        date(2026, 3, 18),
+    );
+    assert_eq!(next, None);
+
+    let next = next_after(
+        "weekly until 2026-03-20 skipping missed",
+        date(2026, 3, 2),
+        date(2026, 3, 18),
+    );
+    assert_eq!(next, None);
+}
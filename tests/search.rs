@@ -0,0 +1,98 @@
+use std::process::Command;
+
+use gyst_tui::search::Search;
+use gyst_tui::task::Task;
+
+fn task(name: &str) -> Task {
+    let mut task = Task::default();
+    task.set_name(name.to_string());
+    task
+}
+
+#[test]
+fn searches_the_name_description_url_and_group() {
+    let search = Search::new("fence", false, false).unwrap();
+    assert!(search.matches(&task("Paint the fence")));
+    assert!(!search.matches(&task("Buy paint")));
+
+    let mut described = task("Buy paint");
+    described.description = Some("White, for the fence".to_string());
+    assert!(search.matches(&described));
+
+    let mut linked = task("Buy paint");
+    linked.url = Some("https://example.com/fence-paint".to_string());
+    assert!(search.matches(&linked));
+
+    let mut grouped = task("Buy paint");
+    grouped.group = Some("fence".to_string());
+    assert!(search.matches(&grouped));
+}
+
+#[test]
+fn plain_text_is_not_a_regex() {
+    let search = Search::new("C++", false, false).unwrap();
+    assert!(search.matches(&task("Learn C++")));
+    assert!(!search.matches(&task("Learn CC")));
+
+    let search = Search::new("^Learn C+$", true, false).unwrap();
+    assert!(search.matches(&task("Learn CC")));
+    assert!(!search.matches(&task("Learn C++")));
+}
+
+#[test]
+fn invalid_regexes_are_errors() {
+    let error = Search::new("fence(", true, false).err().unwrap();
+    assert!(error.to_string().starts_with("Invalid search 'fence('"));
+}
+
+#[test]
+fn case_is_ignored_unless_the_query_has_capitals() {
+    assert!(
+        !Search::new("paint", false, false)
+            .unwrap()
+            .matches(&task("Paint"))
+    );
+    assert!(
+        Search::new("paint", false, true)
+            .unwrap()
+            .matches(&task("Paint"))
+    );
+
+    assert!(Search::smart_case("paint").matches(&task("Paint")));
+    assert!(Search::smart_case("Paint").matches(&task("Paint")));
+    assert!(!Search::smart_case("Paint").matches(&task("paint")));
+}
+
+#[test]
+fn matches_are_found_for_highlighting() {
+    let search = Search::smart_case("paint");
+    assert_eq!(search.find("Paint, then paint again"), vec![0..5, 12..17]);
+    assert!(search.find("Sand the fence").is_empty());
+}
+
+#[test]
+fn ls_shows_matching_tasks() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("tasks.json");
+    let gyst = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_gyst-tui"))
+            .args(args)
+            .env("HOME", dir.path())
+            .env("GYST_DB", &db)
+            .env("GYST_CONFIG_DIR", dir.path().join("config"))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    gyst(&["add", "Buy paint"]);
+    gyst(&["add", "Sand the fence"]);
+
+    let found = gyst(&["ls", "--search", "Paint"]);
+    assert!(!found.contains("Buy paint") && !found.contains("Sand the fence"));
+    let found = gyst(&["ls", "--search", "Paint", "--ignore-case"]);
+    assert!(found.contains("Buy paint") && !found.contains("Sand the fence"));
+    let found = gyst(&["ls", "--search", "^(buy|sand) ", "--regex", "-i"]);
+    assert!(found.contains("Buy paint") && found.contains("Sand the fence"));
+}
@@ -0,0 +1,129 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crossterm::event::KeyCode;
+use gyst_tui::configuration::{KeyBinding, KeyBindings, Settings, load_settings};
+use serde_json::Value;
+
+/// Loads `contents` as a settings file, returning the settings and their unknown fields
+fn load(dir: &Path, contents: &str) -> (Settings, Vec<String>) {
+    let path = dir.join("settings.json");
+    fs::write(&path, contents).unwrap();
+    let (settings, mut unknown) = load_settings(&path).unwrap();
+    unknown.sort();
+    (settings, unknown)
+}
+
+#[test]
+fn missing_settings_are_filled_with_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    let (settings, unknown) = load(
+        dir.path(),
+        r#"{"backup_count": 3, "keybindings": {"quit": "x"}}"#,
+    );
+
+    assert!(unknown.is_empty());
+    assert_eq!(settings.backup_count, 3);
+    assert_eq!(
+        settings.keybindings.quit,
+        KeyBinding::new(KeyCode::Char('x'))
+    );
+    let defaults = KeyBindings::default();
+    assert_eq!(settings.keybindings.down, defaults.down);
+    assert_eq!(settings.keybindings.prev_match, defaults.prev_match);
+    assert_eq!(settings.icons, Settings::default().icons);
+    assert_eq!(settings.workspace, Settings::default().workspace);
+}
+
+#[test]
+fn unknown_settings_are_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let (settings, unknown) = load(
+        dir.path(),
+        r#"{"colour": "red", "keybindings": {"quit": "x", "quitt": "q"},
+            "workspaces": {"work": {"show_complete": false}}}"#,
+    );
+
+    // Workspaces are named by the user, so they are never unknown
+    assert_eq!(unknown, ["colour", "keybindings.quitt"]);
+    assert_eq!(
+        settings.keybindings.quit,
+        KeyBinding::new(KeyCode::Char('x'))
+    );
+    assert!(!settings.workspaces["work"].show_complete);
+}
+
+#[test]
+fn config_migrate_writes_every_setting() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config");
+    fs::create_dir(&config).unwrap();
+    fs::write(
+        config.join("settings.json"),
+        r#"{"backup_count": 3, "colour": "red", "keybindings": {"quit": "x"}}"#,
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gyst-tui"))
+        .args(["config", "migrate"])
+        .env("HOME", dir.path())
+        .env("GYST_DB", dir.path().join("tasks.json"))
+        .env("GYST_CONFIG_DIR", &config)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("Removed unknown setting 'colour'"),
+        "{}",
+        stdout
+    );
+
+    let migrated: Value =
+        serde_json::from_str(&fs::read_to_string(config.join("settings.json")).unwrap()).unwrap();
+    let defaults = serde_json::to_value(Settings::default()).unwrap();
+    let keys = |value: &Value| {
+        value
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(keys(&migrated), keys(&defaults));
+    assert_eq!(
+        keys(&migrated["keybindings"]),
+        keys(&defaults["keybindings"])
+    );
+    assert_eq!(migrated["backup_count"], 3);
+    assert_eq!(migrated["keybindings"]["quit"], "x");
+    assert_eq!(migrated["icons"], defaults["icons"]);
+}
+
+#[test]
+fn unusable_directories_are_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("file");
+    fs::write(&file, "").unwrap();
+    let gyst = |config_dir, db| {
+        let output = Command::new(env!("CARGO_BIN_EXE_gyst-tui"))
+            .arg("ls")
+            .env("HOME", dir.path())
+            .env("GYST_CONFIG_DIR", config_dir)
+            .env("GYST_DB", db)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    // Directories can't be created inside a file
+    let error = gyst(file.join("config"), dir.path().join("tasks.json"));
+    assert!(error.starts_with("Error: Unable to create"), "{}", error);
+    let error = gyst(
+        dir.path().join("config"),
+        file.join("db").join("tasks.json"),
+    );
+    assert!(error.starts_with("Error: Unable to create"), "{}", error);
+}
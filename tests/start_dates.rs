@@ -0,0 +1,64 @@
+use chrono::{Local, NaiveDate, TimeZone};
+
+use gyst_tui::configuration::Settings;
+use gyst_tui::due::Due;
+use gyst_tui::repeat::Repeat;
+use gyst_tui::task::Task;
+use gyst_tui::task_form::TaskForm;
+
+fn day(year: i32, month: u32, day: u32) -> Due {
+    Due::Date(NaiveDate::from_ymd_opt(year, month, day).unwrap())
+}
+
+#[test]
+fn tasks_are_deferred_until_their_start_date() {
+    let mut task = Task::default();
+    task.set_start(Some(day(2026, 11, 16)));
+
+    let before = Local.with_ymd_and_hms(2026, 11, 15, 23, 0, 0).unwrap();
+    let on = Local.with_ymd_and_hms(2026, 11, 16, 0, 0, 0).unwrap();
+    assert!(task.is_deferred(before));
+    assert!(!task.is_deferred(on));
+}
+
+#[test]
+fn tasks_without_a_start_date_have_started() {
+    assert!(!Task::default().is_deferred(Local::now()));
+}
+
+#[test]
+fn tasks_start_at_their_start_time() {
+    let start = Local.with_ymd_and_hms(2026, 11, 16, 9, 0, 0).unwrap();
+    let mut task = Task::default();
+    task.set_start(Some(Due::at(start)));
+
+    assert!(task.is_deferred(start - chrono::Duration::minutes(1)));
+    assert!(!task.is_deferred(start));
+}
+
+#[test]
+fn repeating_tasks_move_their_start_date_along() {
+    let mut task = Task::default();
+    task.set_date(Some(day(2026, 1, 31)));
+    task.set_start(Some(day(2026, 1, 24)));
+    task.set_repeats(Repeat::parse_from_str("every 2 weeks").unwrap());
+
+    let next = task.set_complete().unwrap();
+    assert_eq!(next.date, Some(day(2026, 2, 14)));
+    assert_eq!(next.start, Some(day(2026, 2, 7)));
+}
+
+#[test]
+fn start_dates_after_the_due_date_are_rejected() {
+    let mut form = TaskForm {
+        name: "Renew passport".to_string(),
+        date: "2026-11-16".to_string(),
+        start: "2026-12-16".to_string(),
+        ..TaskForm::default()
+    };
+    assert!(form.submit(&Settings::default()).is_err());
+
+    form.start = "2026-10-16".to_string();
+    let task = form.submit(&Settings::default()).unwrap();
+    assert_eq!(task.start, Some(day(2026, 10, 16)));
+}
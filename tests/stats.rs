@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use uuid::Uuid;
+
+use gyst_tui::due::Due;
+use gyst_tui::stats::Stats;
+use gyst_tui::task::Task;
+
+fn day(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+fn noon(date: NaiveDate) -> DateTime<Local> {
+    Local
+        .from_local_datetime(&date.and_hms_opt(12, 0, 0).unwrap())
+        .unwrap()
+}
+
+/// The occurrences of a series, each due on a day and completed on another one if at all
+fn series(occurrences: &[(NaiveDate, Option<NaiveDate>)]) -> HashMap<usize, Task> {
+    let series = Uuid::new_v4();
+    occurrences
+        .iter()
+        .enumerate()
+        .map(|(id, &(due, completed))| {
+            let mut task = Task::default();
+            task.set_id(Some(id));
+            task.series = Some(series);
+            task.set_date(Some(Due::Date(due)));
+            task.complete = completed.is_some();
+            task.completed_at = completed.map(noon);
+            (id, task)
+        })
+        .collect()
+}
+
+fn streaks(tasks: &HashMap<usize, Task>) -> (usize, usize) {
+    let stats = Stats::for_task(tasks, 0).unwrap();
+    (stats.current_streak, stats.longest_streak)
+}
+
+#[test]
+fn occurrences_completed_on_time_make_a_streak() {
+    let tasks = series(&[
+        (day(2025, 1, 6), Some(day(2025, 1, 6))),
+        (day(2025, 1, 13), Some(day(2025, 1, 12))),
+        (day(2025, 1, 20), Some(day(2025, 1, 20))),
+        (day(2099, 1, 27), None),
+    ]);
+    assert_eq!(streaks(&tasks), (3, 3));
+}
+
+#[test]
+fn late_completions_break_the_streak() {
+    let tasks = series(&[
+        (day(2025, 1, 6), Some(day(2025, 1, 6))),
+        (day(2025, 1, 13), Some(day(2025, 1, 13))),
+        (day(2025, 1, 20), Some(day(2025, 1, 22))),
+        (day(2025, 1, 27), Some(day(2025, 1, 27))),
+    ]);
+    assert_eq!(streaks(&tasks), (1, 2));
+
+    let stats = Stats::for_task(&tasks, 2).unwrap();
+    assert_eq!(stats.completions.len(), 4);
+    assert_eq!(stats.completions[2], noon(day(2025, 1, 22)));
+}
+
+#[test]
+fn missed_occurrences_break_the_streak() {
+    let tasks = series(&[
+        (day(2025, 1, 6), Some(day(2025, 1, 6))),
+        (day(2025, 1, 13), Some(day(2025, 1, 13))),
+        (day(2025, 1, 20), None),
+    ]);
+    assert_eq!(streaks(&tasks), (0, 2));
+
+    let tasks = series(&[
+        (day(2025, 1, 6), Some(day(2025, 1, 6))),
+        (day(2025, 1, 13), Some(day(2025, 1, 13))),
+        (day(2025, 1, 20), None),
+        (day(2025, 1, 27), Some(day(2025, 1, 26))),
+    ]);
+    assert_eq!(streaks(&tasks), (1, 2));
+}
+
+#[test]
+fn tasks_that_do_not_repeat_are_a_series_of_one() {
+    let mut tasks = series(&[(day(2025, 1, 6), Some(day(2025, 1, 6)))]);
+    tasks.get_mut(&0).unwrap().series = None;
+    assert_eq!(streaks(&tasks), (1, 1));
+
+    tasks.get_mut(&0).unwrap().completed_at = Some(noon(day(2025, 1, 7)));
+    assert_eq!(streaks(&tasks), (0, 0));
+
+    let open = series(&[(day(2099, 1, 6), None)]);
+    assert_eq!(streaks(&open), (0, 0));
+    assert!(Stats::for_task(&open, 0).unwrap().completions.is_empty());
+}
+
+#[test]
+fn heatmap_counts_completions_per_day() {
+    let stats = Stats {
+        completions: vec![
+            noon(day(2024, 12, 30)),
+            noon(day(2025, 1, 6)),
+            noon(day(2025, 1, 6)),
+            noon(day(2025, 1, 12)),
+            noon(day(2025, 1, 15)),
+        ],
+        current_streak: 0,
+        longest_streak: 0,
+    };
+    // Two weeks, ending with the week of Wednesday 2025-01-15
+    let rows = stats.heatmap(day(2025, 1, 15), 2);
+
+    assert_eq!(rows.len(), 7);
+    assert_eq!(rows[0], vec![Some(2), Some(0)]);
+    assert_eq!(rows[2], vec![Some(0), Some(1)]);
+    assert_eq!(rows[6], vec![Some(1), None]);
+    assert_eq!(rows[3], vec![Some(0), None]);
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use gyst_tui::store::{JsonStore, TaskStore};
+use gyst_tui::task::Task;
+use gyst_tui::utils;
+
+fn json(dir: &Path) -> Box<dyn TaskStore> {
+    let db = dir.join("tasks.json");
+    if !db.exists() {
+        utils::save_tasks(&db, &HashMap::new(), dir, 0).unwrap();
+    }
+    Box::new(JsonStore::open(&db, &dir.join("backups"), 0).unwrap())
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite(dir: &Path) -> Box<dyn TaskStore> {
+    Box::new(gyst_tui::store::SqliteStore::open(&dir.join("tasks.db")).unwrap())
+}
+
+/// Opens the store of a backend in a directory
+type Open = fn(&Path) -> Box<dyn TaskStore>;
+
+/// Every backend that's built in
+fn backends() -> Vec<Open> {
+    vec![
+        json,
+        #[cfg(feature = "sqlite")]
+        sqlite,
+    ]
+}
+
+fn task(name: &str) -> Task {
+    let mut task = Task::default();
+    task.set_name(name.to_string());
+    task
+}
+
+#[test]
+fn tasks_round_trip() {
+    for open in backends() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = open(dir.path());
+
+        let mut rent = task("Pay rent");
+        rent.set_group("Home".to_string());
+        rent.set_url("https://example.com/rent".to_string());
+        let rent = store.insert(rent).unwrap();
+        let dishes = store.insert(task("Do the dishes")).unwrap();
+        let call = store.insert(task("Call mom")).unwrap();
+        assert_ne!(rent, dishes);
+
+        let mut call_task = store.get(call).unwrap().clone();
+        call_task.set_description("About the holidays".to_string());
+        store.update(call_task).unwrap();
+        let deleted = store.delete(dishes).unwrap().unwrap();
+        assert_eq!(deleted.name, "Do the dishes");
+        assert!(store.delete(dishes).unwrap().is_none());
+
+        let store = open(dir.path());
+        assert_eq!(store.tasks().len(), 2);
+        let rent = store.get(rent).unwrap();
+        assert_eq!(rent.name, "Pay rent");
+        assert_eq!(rent.group.as_deref(), Some("Home"));
+        assert_eq!(rent.url.as_deref(), Some("https://example.com/rent"));
+        let call = store.get(call).unwrap();
+        assert_eq!(call.description.as_deref(), Some("About the holidays"));
+        assert!(store.get(dishes).is_none());
+    }
+}
+
+#[test]
+fn given_ids_are_kept() {
+    for open in backends() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = open(dir.path());
+
+        let mut rent = task("Pay rent");
+        rent.set_id(Some(7));
+        assert_eq!(store.insert(rent).unwrap(), 7);
+        assert_eq!(store.insert(task("Call mom")).unwrap(), 8);
+    }
+}
+
+#[test]
+fn updating_a_missing_task_fails() {
+    for open in backends() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = open(dir.path());
+
+        let mut missing = task("Pay rent");
+        missing.set_id(Some(3));
+        assert!(store.update(missing).is_err());
+        assert!(store.update(task("Pay rent")).is_err());
+        assert!(store.tasks().is_empty());
+    }
+}
+
+#[test]
+fn replacing_all_tasks_keeps_their_ids() {
+    for open in backends() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = open(dir.path());
+        store.insert(task("Pay rent")).unwrap();
+
+        let mut call = task("Call mom");
+        call.set_id(Some(4));
+        store.replace_all(vec![call]).unwrap();
+
+        let store = open(dir.path());
+        assert_eq!(store.tasks().len(), 1);
+        assert_eq!(store.get(4).unwrap().name, "Call mom");
+    }
+}
+
+#[test]
+fn stores_sharing_a_file_see_each_others_changes() {
+    for open in backends() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tui = open(dir.path());
+        let mut cli = open(dir.path());
+
+        let rent = cli.insert(task("Pay rent")).unwrap();
+        assert!(tui.refresh().unwrap());
+        assert_eq!(tui.get(rent).unwrap().name, "Pay rent");
+        assert!(!tui.refresh().unwrap());
+
+        // Writing picks up the other store's changes first, so neither overwrites the other
+        let call = tui.insert(task("Call mom")).unwrap();
+        assert_ne!(call, rent);
+        assert_eq!(tui.tasks().len(), 2);
+        assert!(cli.refresh().unwrap());
+        assert_eq!(cli.get(call).unwrap().name, "Call mom");
+
+        cli.delete(rent).unwrap();
+        assert!(tui.refresh().unwrap());
+        assert!(tui.get(rent).is_none());
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn migrating_copies_every_workspace() {
+    let dir = tempfile::tempdir().unwrap();
+    let gyst = |args: &[&str]| {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_gyst-tui"))
+            .args(args)
+            .env("HOME", dir.path())
+            .env("XDG_DATA_HOME", dir.path().join("data"))
+            .env("GYST_CONFIG_DIR", dir.path().join("config"))
+            .env_remove("GYST_DB")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    gyst(&["add", "Buy paint"]);
+    gyst(&["workspace", "new", "garden"]);
+    gyst(&["workspace", "switch", "garden"]);
+    gyst(&["add", "Plant tulips"]);
+    gyst(&["db", "migrate", "--to", "sqlite"]);
+
+    assert!(
+        dir.path()
+            .join("data/gyst-tui/workspaces/garden/tasks.db")
+            .exists()
+    );
+    assert!(gyst(&["ls"]).contains("Plant tulips"));
+    gyst(&["workspace", "switch", "main"]);
+    assert!(gyst(&["ls"]).contains("Buy paint"));
+}
+
+#[test]
+fn given_databases_are_used_exactly_as_named() {
+    for name in ["work.tasks", "mylist"] {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join(name);
+        let gyst = |args: &[&str]| {
+            let output = std::process::Command::new(env!("CARGO_BIN_EXE_gyst-tui"))
+                .args(args)
+                .env("HOME", dir.path())
+                .env("GYST_DB", &db)
+                .env("GYST_CONFIG_DIR", dir.path().join("config"))
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{:?}", output);
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        gyst(&["add", "Buy paint"]);
+        gyst(&["add", "Sand the fence"]);
+        assert!(fs::read_to_string(&db).unwrap().contains("Sand the fence"));
+        assert!(gyst(&["ls"]).contains("Buy paint"));
+
+        let mut files = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        files.sort();
+        // Backups, history and lock are named after the whole file name
+        let mut expected = vec![
+            name.to_string(),
+            format!("{}.backups", name),
+            format!("{}.history", name),
+            format!("{}.lock", name),
+            "config".to_string(),
+        ];
+        expected.sort();
+        assert_eq!(files, expected);
+    }
+}
@@ -0,0 +1,41 @@
+use gyst_tui::task::{self, Task};
+
+fn task(id: usize, parent: Option<usize>) -> Task {
+    let mut task = Task::default();
+    task.set_id(Some(id));
+    task.set_parent(parent);
+    task
+}
+
+fn ids(tree: &[(&Task, usize)]) -> Vec<(usize, usize)> {
+    tree.iter()
+        .map(|(t, depth)| (t.id.unwrap(), *depth))
+        .collect()
+}
+
+#[test]
+fn subtasks_come_right_after_their_parent() {
+    let tasks = [
+        task(1, None),
+        task(2, Some(3)),
+        task(3, Some(1)),
+        task(4, None),
+        task(5, Some(1)),
+    ];
+    let tree = task::as_tree(tasks.iter().collect());
+    assert_eq!(ids(&tree), vec![(1, 0), (3, 1), (2, 2), (5, 1), (4, 0)]);
+}
+
+#[test]
+fn subtasks_without_their_parent_are_at_the_top() {
+    let tasks = [task(2, Some(1)), task(3, Some(2))];
+    let tree = task::as_tree(tasks.iter().collect());
+    assert_eq!(ids(&tree), vec![(2, 0), (3, 1)]);
+}
+
+#[test]
+fn tasks_in_a_loop_are_still_listed() {
+    let tasks = [task(1, Some(2)), task(2, Some(1)), task(3, Some(3))];
+    let tree = task::as_tree(tasks.iter().collect());
+    assert_eq!(ids(&tree), vec![(3, 0), (1, 0), (2, 1)]);
+}
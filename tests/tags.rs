@@ -0,0 +1,41 @@
+use gyst_tui::configuration::Settings;
+use gyst_tui::task::Task;
+use gyst_tui::task_form::TaskForm;
+
+fn submit(name: &str, tags: &str) -> Task {
+    let mut form = TaskForm {
+        name: name.to_string(),
+        tags: tags.to_string(),
+        ..TaskForm::default()
+    };
+    form.submit(&Settings::default()).unwrap()
+}
+
+#[test]
+fn tags_are_taken_from_the_name() {
+    let task = submit("Buy milk +errand #Home", "");
+    assert_eq!(task.name, "Buy milk");
+    assert_eq!(task.tags, vec!["errand", "Home"]);
+}
+
+#[test]
+fn numbers_and_symbols_are_not_tags() {
+    let task = submit("Fix bug #1 in C++ +1", "");
+    assert_eq!(task.name, "Fix bug #1 in C++ +1");
+    assert!(task.tags.is_empty());
+}
+
+#[test]
+fn tags_are_merged_without_duplicates() {
+    let task = submit("Call mom #phone", "phone, #home family");
+    assert_eq!(task.tags, vec!["phone", "home", "family"]);
+    assert!(task.has_tag("HOME"));
+}
+
+#[test]
+fn editing_a_task_keeps_its_tags() {
+    let task = submit("Buy milk +errand", "home");
+    let mut form = TaskForm::from_task(&task, &Settings::default());
+    assert_eq!(form.name, "Buy milk");
+    assert_eq!(form.submit(&Settings::default()).unwrap().tags, task.tags);
+}
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use gyst_tui::date_parser;
+use gyst_tui::due::Due;
+use gyst_tui::error::LoadError;
+use gyst_tui::repeat::Repeat;
+use gyst_tui::task::Task;
+use gyst_tui::utils;
+
+fn due_in(zone: Tz, (year, month, day): (i32, u32, u32), (hour, minute): (u32, u32)) -> Due {
+    let naive = NaiveDate::from_ymd_opt(year, month, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap();
+    Due::from_local(naive, Some(zone)).unwrap()
+}
+
+fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+        .unwrap()
+}
+
+/// The instants the occurrences after `start` are due at
+fn next_instants(start: Due, repeats: &str, count: usize) -> Vec<DateTime<Utc>> {
+    let mut task = Task::default();
+    task.set_date(Some(start));
+    task.set_repeats(Repeat::parse_from_str(repeats).unwrap());
+
+    (0..count)
+        .map(|_| {
+            task = task.set_complete().unwrap();
+            match task.date.unwrap() {
+                Due::DateTime { at, zone } => {
+                    assert_eq!(zone, start_zone(&start));
+                    at
+                }
+                Due::Date(_) => panic!("Task should still be due at a time"),
+            }
+        })
+        .collect()
+}
+
+fn start_zone(due: &Due) -> Option<Tz> {
+    match due {
+        Due::DateTime { zone, .. } => *zone,
+        Due::Date(_) => None,
+    }
+}
+
+#[test]
+fn daily_tasks_keep_their_time_when_the_clocks_go_back() {
+    // Berlin goes from UTC+2 to UTC+1 on October 25th 2026
+    let start = due_in(Tz::Europe__Berlin, (2026, 10, 24), (9, 0));
+    assert_eq!(
+        next_instants(start, "daily", 2),
+        vec![utc(2026, 10, 25, 8, 0), utc(2026, 10, 26, 8, 0)]
+    );
+}
+
+#[test]
+fn weekly_tasks_keep_their_time_when_the_clocks_go_forward() {
+    // New York goes from UTC-5 to UTC-4 on March 14th 2027
+    let start = due_in(Tz::America__New_York, (2027, 3, 8), (9, 0));
+    assert_eq!(
+        next_instants(start, "weekly", 2),
+        vec![utc(2027, 3, 15, 13, 0), utc(2027, 3, 22, 13, 0)]
+    );
+}
+
+#[test]
+fn skipped_times_are_due_an_hour_later() {
+    // 02:30 doesn't exist in New York on March 14th 2027
+    let start = due_in(Tz::America__New_York, (2027, 3, 13), (2, 30));
+    assert_eq!(
+        next_instants(start, "daily", 1),
+        vec![utc(2027, 3, 14, 7, 30)]
+    );
+}
+
+#[test]
+fn repeated_times_are_due_the_first_time() {
+    // 01:30 happens twice in New York on November 1st 2026
+    let start = due_in(Tz::America__New_York, (2026, 10, 31), (1, 30));
+    assert_eq!(
+        next_instants(start, "daily", 1),
+        vec![utc(2026, 11, 1, 5, 30)]
+    );
+}
+
+#[test]
+fn dates_can_name_a_time_zone() {
+    let now = Local.with_ymd_and_hms(2026, 10, 16, 10, 0, 0).unwrap();
+    let due = date_parser::parse("2026-10-24 09:00 Europe/Berlin", now).unwrap();
+    assert_eq!(
+        due,
+        Due::DateTime {
+            at: utc(2026, 10, 24, 7, 0),
+            zone: Some(Tz::Europe__Berlin)
+        }
+    );
+
+    let due = date_parser::parse("tomorrow 9am America/New_York", now).unwrap();
+    assert_eq!(start_zone(&due), Some(Tz::America__New_York));
+    assert_eq!(due, due_in(Tz::America__New_York, (2026, 10, 17), (9, 0)));
+}
+
+#[test]
+fn invalid_dates_are_errors() {
+    let path = Path::new("tasks.json");
+    let task = |date: &str| {
+        format!(
+            r#"{{"schema_version":{},"tasks":{{"1":{{"id":1,"series":null,"name":"Task","date":{},"start":null,"repeats":"Never","priority":"None","group":null,"tags":[],"blocked_by":[],"description":null,"url":null,"complete":false,"completed_at":null}}}}}}"#,
+            utils::SCHEMA_VERSION,
+            date
+        )
+    };
+
+    let valid = task(r#"{"DateTime":{"at":"2026-10-24T07:00:00Z","zone":"Europe/Berlin"}}"#);
+    assert!(utils::parse_tasks(&valid, path).is_ok());
+
+    for date in [
+        r#"{"DateTime":{"at":"not a date","zone":null}}"#,
+        r#"{"DateTime":{"at":"2026-10-24T07:00:00Z","zone":"Mars/Olympus_Mons"}}"#,
+        r#"{"Date":"2026-02-30"}"#,
+    ] {
+        let err = utils::parse_tasks(&task(date), path).unwrap_err();
+        assert!(matches!(err, LoadError::Parse { .. }), "{}", date);
+    }
+}
@@ -0,0 +1,27 @@
+use std::process::Command;
+
+#[test]
+fn workspaces_are_not_used_with_a_given_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_gyst-tui"))
+        .args(["workspace", "new", "work"])
+        .env("HOME", dir.path())
+        .env("GYST_DB", dir.path().join("tasks.json"))
+        .env("GYST_CONFIG_DIR", dir.path().join("config"))
+        .env_remove("XDG_DATA_HOME")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        error.contains("Workspaces can't be used with --db"),
+        "{}",
+        error
+    );
+    assert!(
+        !dir.path()
+            .join(".local/share/gyst-tui/workspaces/work")
+            .exists()
+    );
+}